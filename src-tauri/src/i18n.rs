@@ -0,0 +1,669 @@
+//! 利用者に表示するメッセージの翻訳テーブル
+
+use std::cell::Cell;
+use std::fmt::Display;
+use std::future::Future;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::task::{Context, Poll};
+
+use serde::{Deserialize, Serialize};
+
+/// メッセージの表示言語
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    #[default]
+    Ja,
+    En,
+}
+
+/// 設定に結び付かない表示 (ダイアログやメニューなど) の言語。画面で設定ファイルを読み込むたびに更新する
+static DEFAULT: AtomicU8 = AtomicU8::new(Language::Ja as u8);
+
+thread_local! {
+    /// このスレッドで実行中の処理の言語。use_language の間だけ設定され、無ければ DEFAULT を使う
+    static CURRENT: Cell<Option<Language>> = const { Cell::new(None) };
+}
+
+pub fn set_default_language(language: Language) {
+    DEFAULT.store(language as u8, Ordering::Relaxed);
+}
+
+pub fn language() -> Language {
+    CURRENT.get().unwrap_or_else(|| match DEFAULT.load(Ordering::Relaxed) {
+        x if x == Language::En as u8 => Language::En,
+        _ => Language::Ja,
+    })
+}
+
+/// use_language が返す値。破棄するとスレッドの言語を元に戻す。
+/// 別のスレッドで再開する await をまたいで保持できないよう Send にしない
+pub struct LanguageGuard {
+    previous: Option<Language>,
+    _not_send: PhantomData<*const ()>,
+}
+
+impl Drop for LanguageGuard {
+    fn drop(&mut self) {
+        CURRENT.set(self.previous);
+    }
+}
+
+/// 戻り値を破棄するまで、このスレッドのメッセージを `language` にする。
+/// 同時に実行中の別の設定の処理には影響しない。スレッドを起動した場合は、そのスレッドでも改めて呼ぶ
+pub fn use_language(language: Language) -> LanguageGuard {
+    LanguageGuard { previous: CURRENT.replace(Some(language)), _not_send: PhantomData }
+}
+
+/// poll するたびに `language` を設定する Future。await をまたぐ処理を 1 つの言語で実行する
+pub struct Scoped<F> {
+    language: Language,
+    future: Pin<Box<F>>,
+}
+
+impl<F: Future> Future for Scoped<F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<F::Output> {
+        let _language = use_language(self.language);
+        self.future.as_mut().poll(cx)
+    }
+}
+
+/// `future` を `language` で実行する
+pub fn scoped<F: Future>(language: Language, future: F) -> Scoped<F> {
+    Scoped { language, future: Box::pin(future) }
+}
+
+/// メッセージの種類。`{}` は tr! に渡した引数で順に置き換えられる
+#[derive(Debug, Clone, Copy)]
+pub enum Msg {
+    // 設定ファイル
+    ConfigReadFailed,
+    YamlParseFailed,
+    JsonParseFailed,
+    TomlParseFailed,
+    InvalidRegex,
+    InvalidPatterns,
+
+    // 設定の検証
+    RuleNameDuplicate,
+    PatternEmpty,
+    NoSourceFolder,
+    DestDriveNotFound,
+    DestNotFolder,
+    DestMetadataFailed,
+    DestReadOnly,
+
+    // 整理処理
+    RunStarted,
+    DryRunTag,
+    PathExpanded,
+    UnexpectedRuleError,
+    LogError,
+    LogWarning,
+    LogDirCreateFailed,
+    LogOpenFailed,
+    LoggingDisabled,
+    LogFileMissing,
+    LogEditorFailed,
+    ConfigFileMissing,
+    ConfigPathNotFile,
+    ConfigEditorFailed,
+    RuleHeader,
+    RuleDisabled,
+    RuleIndexOutOfRange,
+    CopySuffix,
+    RuleNameEmpty,
+    RuleNameTaken,
+    RuleNotFound,
+    SourceFolderMissing,
+    SourceGlobNoMatch,
+    SourceGlobInvalid,
+    SourceIsDestination,
+    DestinationInsideSource,
+    InsufficientSpace,
+    DestFolderCreateFailed,
+    DestinationMissing,
+    DestinationNotWritable,
+    NetworkUnreachable,
+    NetworkTimeout,
+    FileMatched,
+    FileSkipped,
+    Excluded,
+    ClaimedByEarlierRule,
+    Cancelled,
+    RunLimitReached,
+    KeepAtLeastReached,
+    CategoryImages,
+    CategoryDocuments,
+    CategoryVideos,
+    CategoryMusic,
+    CategoryArchives,
+    CategoryInstallers,
+    SuggestedPrefixRule,
+    SuggestedExtensionRule,
+    CompressWithLink,
+    CompressFolder,
+    CompressFailed,
+    ArchiveFailed,
+    ArchiveWritten,
+    DryRunArchivePlanned,
+    DeleteAfterArchiveFailed,
+    ConfirmDeclined,
+    ConfirmUnattended,
+    PostRunFinished,
+    PostRunFailed,
+    NoPendingConfirmation,
+    NoResultsToExport,
+    ExportFailed,
+    RolledBack,
+    RolledBackRemoved,
+    TransactionRolledBack,
+    RollbackIncomplete,
+    Renamed,
+    Sanitized,
+    DateFolder,
+    Flattened,
+    DestinationResolved,
+    CaptureGroupMissing,
+    InvalidCaptureFolder,
+    ConflictOverwrite,
+    ConflictSkip,
+    ConflictRename,
+    KeepNewerOverwrite,
+    KeepNewerSkip,
+    OlderSourceDeletePlanned,
+    OlderSourceDeleted,
+    OlderSourceDeleteFailed,
+    ModifiedTimeFailed,
+    SidecarWriteFailed,
+    DuplicateRemoved,
+    DuplicatePlanned,
+    DuplicateSkipped,
+    DuplicateRemoveFailed,
+    DuplicateCheckFailed,
+    DryRunPlanned,
+    DryRunCopyPlanned,
+    DryRunLinkPlanned,
+    Linked,
+    SymlinkFailed,
+    SymlinkPrivilege,
+    HardlinkFailed,
+    HardlinkCrossVolume,
+    HardlinkFolder,
+    DestinationIsFolder,
+    DestSubfolderCreateFailed,
+    Moved,
+    Copied,
+    DryRunSummary,
+    MovedSummary,
+    UnmatchedRuleName,
+    UnmatchedHeader,
+    Quarantined,
+    QuarantinePlanned,
+    ConfigDirCreateFailed,
+    JournalSerializeFailed,
+    JournalWriteFailed,
+    HistoryReadFailed,
+    HistoryParseFailed,
+    HistoryWriteFailed,
+    LastRunWriteFailed,
+
+    // フィルタ
+    ExtensionExcluded,
+    ContentTypeExcluded,
+    ContentTypeUnavailable,
+    UnknownContentType,
+    SizeUnavailable,
+    SizeBelowMin,
+    SizeAboveMax,
+    ModifiedUnavailable,
+    AgeBelowMin,
+    AgeAboveMax,
+    NotModifiedSinceLastRun,
+
+    // 移動先の決定
+    InvalidRenameResult,
+    MetadataFailed,
+    FileTimeUnavailable,
+    ModifiedTime,
+    CreatedTime,
+    InvalidDateFormat,
+    InvalidDateFolder,
+    FolderReadFailed,
+    IgnoreFileReadFailed,
+    InvalidIgnorePattern,
+    EntryReadFailed,
+    FileTypeFailed,
+
+    // 移動
+    MoveIntoItself,
+    MoveFailed,
+    CopyFailed,
+    CopyMismatch,
+    VerifyFailed,
+    RestoreTimesFailed,
+    TrashFailed,
+    DeleteAfterCopyFailed,
+    PartialCopyRemoved,
+    EmptyDirRemoved,
+    HiddenSkipped,
+    NotUnderStructureBase,
+    FlattenIgnored,
+    NoSchedule,
+    InvalidSchedule,
+    ScheduleNeverRuns,
+    ScheduledRun,
+    NoConfigFiles,
+    DuplicateRuleAcrossFiles,
+    SandboxCreateFailed,
+    SandboxReproduceFailed,
+    EmptyDirRemoveFailed,
+    EmptyDirScanFailed,
+    PartialCopyRemoveFailed,
+    RetryInUse,
+    SkippedInUse,
+
+    // バックアップ・復元
+    NoLastConfig,
+    BackupSourceMissing,
+    BackupFolderReadFailed,
+    InvalidBackupTimestampFormat,
+    OldBackupRemoveFailed,
+    BackupFailed,
+    BackupDone,
+    OldBackupsRemoved,
+    InvalidFolderSelected,
+    BackupCancelled,
+    InvalidFileSelected,
+    NotBackupFile,
+    RestoreConfirm,
+    RestoreTitle,
+    RestoreCancelled,
+    SafetyBackupFailed,
+    SafetyBackupDone,
+    RestoreFailed,
+    RestoreDone,
+
+    // 設定の保存
+    SaveInvalidPatterns,
+    YamlSerializeFailed,
+    JsonSerializeFailed,
+    TomlSerializeFailed,
+    ConfigBackupFailed,
+    ConfigSaveFailed,
+
+    // 取り消し
+    NoUndoRecord,
+    UndoRecordReadFailed,
+    UndoRecordParseFailed,
+    UndoRecordRemoveFailed,
+    UndoDestinationMissing,
+    UndoModified,
+    UndoOriginalExists,
+    UndoFolderCreateFailed,
+    UndoRemoveFailed,
+    UndoExtractFailed,
+
+    // フォルダ監視
+    NoWatchableFolders,
+    WatchStartFailed,
+    WatchFolderFailed,
+    AutoOrganizeHeader,
+
+    // ダイアログ・メニュー
+    PathMissing,
+    FileManagerFailed,
+    FolderSelectCancelled,
+    ConfigFileFilter,
+    PathConvertFailed,
+    FileSelectCancelled,
+    MenuBackupRules,
+    MenuRestoreRules,
+    ConfigDirNotFound,
+    LastConfigCreateFailed,
+    LastConfigWriteFailed,
+    RecentConfigsReadFailed,
+    RecentConfigsSerializeFailed,
+    RecentConfigsWriteFailed,
+    RecentConfigsRemoveFailed,
+}
+
+impl Msg {
+    /// (日本語, 英語) のテンプレート
+    fn templates(self) -> (&'static str, &'static str) {
+        use Msg::*;
+        match self {
+            ConfigReadFailed => ("設定ファイルの読み込みに失敗しました: {}", "Failed to read the config file: {}"),
+            YamlParseFailed => ("YAML解析に失敗しました: {}", "Failed to parse YAML: {}"),
+            JsonParseFailed => ("JSON解析に失敗しました: {}", "Failed to parse JSON: {}"),
+            TomlParseFailed => ("TOML解析に失敗しました: {}", "Failed to parse TOML: {}"),
+            InvalidRegex => ("正規表現が無効です ({}): {}", "Invalid regular expression ({}): {}"),
+            InvalidPatterns => ("無効な正規表現があります:\n{}", "Some regular expressions are invalid:\n{}"),
+
+            RuleNameDuplicate => ("ルール名 '{}' が重複しています", "The rule name '{}' is used more than once"),
+            PatternEmpty => ("パターンが空のため、すべてのファイルにマッチします", "The pattern is empty, so it matches every file"),
+            NoSourceFolder => ("ソースフォルダが指定されていません", "No source folder is specified"),
+            DestDriveNotFound => ("宛先フォルダのドライブが見つかりません: {}", "The drive of the destination folder was not found: {}"),
+            DestNotFolder => ("宛先パスにフォルダ以外のファイルが存在します: {}", "The destination path is a file, not a folder: {}"),
+            DestMetadataFailed => ("宛先フォルダの情報を取得できません {}: {}", "Cannot read the destination folder information {}: {}"),
+            DestReadOnly => ("宛先フォルダが読み取り専用です: {}", "The destination folder is read-only: {}"),
+
+            RunStarted => ("=== 実行開始: {}{} ===", "=== Run started: {}{} ==="),
+            DryRunTag => (" [シミュレーション]", " [dry run]"),
+            PathExpanded => ("パス展開 ({}): {} -> {}", "Expanded path ({}): {} -> {}"),
+            UnexpectedRuleError => ("ルールの実行中に予期しないエラーが発生しました", "An unexpected error occurred while running the rules"),
+            LogError => ("エラー: {}", "Error: {}"),
+            LogWarning => ("警告: {}", "Warning: {}"),
+            LogDirCreateFailed => ("ログフォルダの作成に失敗しました: {}", "Failed to create the log folder: {}"),
+            LogOpenFailed => ("ログファイルの作成/オープンに失敗しました: {}", "Failed to create/open the log file: {}"),
+            LoggingDisabled => ("ログの出力が無効になっています (logging: false)", "Logging is disabled (logging: false)"),
+            LogFileMissing => ("ログファイルがまだありません: {}", "The log file does not exist yet: {}"),
+            LogEditorFailed => ("ログファイルを開けませんでした: {}", "Failed to open the log file: {}"),
+            ConfigFileMissing => (
+                "設定ファイルが見つかりません。移動または削除された可能性があります: {}",
+                "The config file was not found. It may have been moved or deleted: {}",
+            ),
+            ConfigPathNotFile => ("設定ファイルではなくフォルダが指定されています: {}", "The path is a folder, not a config file: {}"),
+            ConfigEditorFailed => ("設定ファイルを開けませんでした: {}", "Failed to open the config file: {}"),
+            RuleHeader => ("--- ルール: {} (パターン: {}) ---", "--- Rule: {} (pattern: {}) ---"),
+            RuleDisabled => ("ルール '{}' はスキップされました (無効)", "Rule '{}' was skipped (disabled)"),
+            RuleIndexOutOfRange => ("指定された番号のルールがありません: {}", "There is no rule at index {}"),
+            CopySuffix => ("コピー", "copy"),
+            RuleNameEmpty => ("ルール名が空です", "The rule name is empty"),
+            RuleNameTaken => ("ルール名 '{}' は既に使われています", "The rule name '{}' is already in use"),
+            RuleNotFound => ("ルール '{}' がありません", "There is no rule named '{}'"),
+            SourceFolderMissing => ("ソースフォルダが存在しません: {}", "Source folder does not exist: {}"),
+            SourceGlobInvalid => ("ルール '{}': ソースフォルダの glob が不正です: {} ({})", "Rule '{}': invalid source folder glob: {} ({})"),
+            SourceGlobNoMatch => ("ルール '{}': ソースフォルダの glob にマッチするフォルダがありません: {}", "Rule '{}': no folders match the source folder glob: {}"),
+            SourceIsDestination => ("ルール '{}' のソースと宛先が同じフォルダのためスキップしました: {}", "Rule '{}' skipped because the source and destination are the same folder: {}"),
+            DestinationInsideSource => ("ルール '{}' の宛先 {} が再帰走査するソース {} の中にあるためスキップしました", "Rule '{}' skipped because the destination {} is inside the recursively scanned source {}"),
+            InsufficientSpace => ("ルール '{}' をスキップしました: 宛先に {} バイト必要ですが、空き容量は {} バイトです", "Rule '{}' skipped: {} bytes are needed at the destination but only {} bytes are free"),
+            DestFolderCreateFailed => ("宛先フォルダの作成に失敗しました: {}", "Failed to create the destination folder: {}"),
+            DestinationMissing => (
+                "ルール '{}': 宛先フォルダが存在しません: {} (create_destination が false のためスキップしました)",
+                "Rule '{}': the destination folder does not exist: {} (skipped because create_destination is false)",
+            ),
+            DestinationNotWritable => ("宛先に書き込めません: {} ({})", "Cannot write to the destination: {} ({})"),
+            NetworkUnreachable => ("ルール '{}': ネットワーク宛先に到達できません: {} ({})", "Rule '{}': the network destination is unreachable: {} ({})"),
+            NetworkTimeout => ("{} ミリ秒以内に応答がありませんでした", "no response within {} ms"),
+            FileMatched => ("ファイル: {} → マッチ: {}", "File: {} → matched: {}"),
+            FileSkipped => ("スキップ: {} ({})", "Skipped: {} ({})"),
+            Excluded => ("除外: {}", "Excluded: {}"),
+            ClaimedByEarlierRule => (
+                "スキップ: {} は先のルール '{}' の対象です (first_match_wins)",
+                "Skipped: {} is already handled by the earlier rule '{}' (first_match_wins)",
+            ),
+            Cancelled => ("キャンセルされました", "Cancelled"),
+            RunLimitReached => (
+                "1 回の実行の上限 (max_files_per_run / max_total_bytes_per_run) に達したため中止しました。ルール '{}' の残り {} 件は処理していません",
+                "Stopped because the per-run limit (max_files_per_run / max_total_bytes_per_run) was reached; rule '{}' has {} file(s) left unprocessed",
+            ),
+            CategoryImages => ("画像", "Images"),
+            CategoryDocuments => ("書類", "Documents"),
+            CategoryVideos => ("動画", "Videos"),
+            CategoryMusic => ("音楽", "Music"),
+            CategoryArchives => ("圧縮ファイル", "Archives"),
+            CategoryInstallers => ("インストーラ", "Installers"),
+            SuggestedPrefixRule => ("'{}' で始まるファイル", "Files starting with '{}'"),
+            SuggestedExtensionRule => (".{} ファイル", ".{} files"),
+            KeepAtLeastReached => (
+                "ルール '{}': ソースに {} 件以上残すため (keep_at_least)、残り {} 件は移動せずそのままにしました",
+                "Rule '{}': keeping at least {} file(s) in the source (keep_at_least), so the remaining {} were left in place",
+            ),
+            PostRunFinished => ("実行後コマンド: {} (終了コード {})", "Post-run command: {} (exit code {})"),
+            PostRunFailed => ("実行後コマンドを起動できませんでした: {}", "Failed to start the post-run command: {}"),
+            ConfirmUnattended => (
+                "ルール '{}' は {} 件を移動するため確認が必要ですが、監視やスケジュールによる自動整理では確認できないためスキップしました",
+                "Rule '{}' would move {} files and needs confirmation, so it was skipped during automatic organizing",
+            ),
+            ConfirmDeclined => ("ルール '{}' は {} 件を移動するため確認しましたが、承認されなかったためスキップしました", "Rule '{}' would move {} files and was skipped because it was not confirmed"),
+            NoResultsToExport => ("書き出す整理結果がありません", "There are no results to export"),
+            ExportFailed => ("整理結果の書き出しに失敗しました: {}", "Failed to export the results: {}"),
+            NoPendingConfirmation => ("確認待ちの要求がありません: {}", "No request is waiting for confirmation: {}"),
+            RolledBack => ("元に戻しました: {} -> {}", "Rolled back: {} -> {}"),
+            RolledBackRemoved => ("作成したファイルを削除しました: {}", "Removed the created file: {}"),
+            TransactionRolledBack => (
+                "エラーが発生したため、この実行で移動したファイル {} 件を元に戻し、作成したファイル {} 件を削除しました: {}",
+                "An error occurred, so {} files moved in this run were restored and {} created files were removed: {}",
+            ),
+            RollbackIncomplete => ("元に戻せなかったファイル:\n{}", "Files that could not be restored:\n{}"),
+            Renamed => ("リネーム: {} -> {}", "Renamed: {} -> {}"),
+            Sanitized => ("ファイル名の整形: {} -> {}", "Sanitized file name: {} -> {}"),
+            DateFolder => ("日付フォルダ: {} ({}を使用)", "Date folder: {} (using {})"),
+            Flattened => ("フラット化: {}", "Flattened: {}"),
+            DestinationResolved => ("宛先フォルダ: {}", "Destination folder: {}"),
+            CaptureGroupMissing => ("宛先のグループ '{}' がマッチしていません", "group '{}' used in the destination did not match"),
+            InvalidCaptureFolder => ("宛先のグループ '{}' の値がフォルダ名として不正です: '{}'", "group '{}' used in the destination is not a valid folder name: '{}'"),
+            ConflictOverwrite => ("競合 (overwrite): {} を上書きします", "Conflict (overwrite): overwriting {}"),
+            ConflictSkip => ("競合 (skip): {} は既に存在するためスキップしました", "Conflict (skip): {} already exists, skipped"),
+            ConflictRename => ("競合 (rename): {} -> {}", "Conflict (rename): {} -> {}"),
+            KeepNewerOverwrite => (
+                "競合 (keep_newer): 元ファイルの方が新しいため {} を上書きします (元: {} / 宛先: {})",
+                "Conflict (keep_newer): the source is newer, overwriting {} (source: {} / destination: {})",
+            ),
+            KeepNewerSkip => (
+                "競合 (keep_newer): 宛先の方が新しいため {} をスキップしました (元: {} / 宛先: {})",
+                "Conflict (keep_newer): the destination is newer, skipped {} (source: {} / destination: {})",
+            ),
+            OlderSourceDeletePlanned => ("古い元ファイルは削除予定", "the older source would be removed"),
+            OlderSourceDeleted => ("古い元ファイルを削除しました", "removed the older source"),
+            OlderSourceDeleteFailed => ("古い元ファイルの削除に失敗 {}: {}", "Failed to remove the older source {}: {}"),
+            SidecarWriteFailed => ("メタデータファイルを書き込めませんでした {}: {}", "Failed to write the metadata file {}: {}"),
+            ModifiedTimeFailed => ("更新日時を比較できませんでした {} / {}: {}", "Failed to compare the modified times of {} and {}: {}"),
+            DuplicateRemoved => ("競合 (dedupe): {} は {} と同じ内容のため削除しました", "Conflict (dedupe): {} is identical to {}, removed"),
+            DuplicatePlanned => ("[シミュレーション] 競合 (dedupe): {} は {} と同じ内容のため削除予定", "[dry run] Conflict (dedupe): {} is identical to {}, would remove"),
+            DuplicateSkipped => ("競合 (dedupe): {} は {} と同じ内容のためスキップしました", "Conflict (dedupe): {} is identical to {}, skipped"),
+            DuplicateRemoveFailed => ("重複ファイルの削除に失敗 {}: {}", "Failed to remove duplicate {}: {}"),
+            DuplicateCheckFailed => ("重複の確認に失敗 {}: {}", "Failed to compare {} for duplicates: {}"),
+            DryRunPlanned => ("[シミュレーション] 移動予定: {} -> {}", "[dry run] Would move: {} -> {}"),
+            DryRunCopyPlanned => ("[シミュレーション] コピー予定: {} -> {}", "[dry run] Would copy: {} -> {}"),
+            DryRunLinkPlanned => ("[シミュレーション] リンク作成予定: {} -> {}", "[dry run] Would link: {} -> {}"),
+            Linked => ("リンクを作成 ({}): {} -> {}", "Linked ({}): {} -> {}"),
+            SymlinkFailed => ("シンボリックリンクの作成に失敗しました {} -> {}: {}", "Failed to create the symbolic link {} -> {}: {}"),
+            SymlinkPrivilege => (
+                "シンボリックリンクを作成する権限がありません: {} (管理者として実行するか開発者モードを有効にしてください。同じドライブ内なら mode: hardlink で代用できます)",
+                "Not permitted to create the symbolic link: {} (run as administrator or enable Developer Mode; within the same drive, mode: hardlink can be used instead)",
+            ),
+            HardlinkFailed => ("ハードリンクの作成に失敗しました {} -> {}: {}", "Failed to create the hard link {} -> {}: {}"),
+            HardlinkCrossVolume => (
+                "ハードリンクは同一ボリューム内のみ可能です: {} -> {}",
+                "Hard links are only possible within the same volume: {} -> {}",
+            ),
+            HardlinkFolder => ("フォルダはハードリンクにできません", "folders cannot be hard-linked"),
+            DestinationIsFolder => ("宛先に同名のフォルダがあります", "a folder with the same name exists at the destination"),
+            DestSubfolderCreateFailed => ("宛先フォルダの作成に失敗しました {}: {}", "Failed to create the destination folder {}: {}"),
+            Moved => ("移動 ({}): {} -> {}", "Moved ({}): {} -> {}"),
+            Copied => ("コピー: {} -> {}", "Copied: {} -> {}"),
+            CompressWithLink => (
+                "ルール '{}': compress はリンクのモード (symlink / hardlink) では使えません",
+                "Rule '{}': compress cannot be used with the link modes (symlink / hardlink)",
+            ),
+            CompressFolder => ("フォルダは圧縮できません: {}", "Folders cannot be compressed: {}"),
+            CompressFailed => ("圧縮に失敗しました {}: {}", "Failed to compress {}: {}"),
+            ArchiveFailed => ("アーカイブの作成に失敗しました {}: {}", "Failed to create the archive {}: {}"),
+            ArchiveWritten => ("圧縮: {} ({} 件, 元のサイズの {}%)", "Compressed: {} ({} file(s), {}% of the original size)"),
+            DryRunArchivePlanned => ("[シミュレーション] {}個のファイルをアーカイブ予定: {}", "[dry run] Would archive {} file(s): {}"),
+            DeleteAfterArchiveFailed => ("圧縮後の削除失敗 {}: {}", "Failed to delete after compressing {}: {}"),
+            DryRunSummary => ("[シミュレーション] ルール '{}': {}個のファイルが移動予定です", "[dry run] Rule '{}': {} file(s) would be moved"),
+            MovedSummary => ("ルール '{}': {}個のファイルを移動しました", "Rule '{}': moved {} file(s)"),
+            UnmatchedRuleName => ("(未分類)", "(unmatched)"),
+            UnmatchedHeader => ("--- 未分類のファイル -> {} ---", "--- Unmatched files -> {} ---"),
+            Quarantined => ("未分類として移動 ({}): {} -> {}", "Moved as unmatched ({}): {} -> {}"),
+            QuarantinePlanned => ("[シミュレーション] 未分類として移動予定: {} -> {}", "[dry run] Would move as unmatched: {} -> {}"),
+            ConfigDirCreateFailed => ("設定ディレクトリの作成に失敗しました: {}", "Failed to create the settings directory: {}"),
+            JournalSerializeFailed => ("取り消し用の記録の変換に失敗しました: {}", "Failed to serialize the undo record: {}"),
+            JournalWriteFailed => ("取り消し用の記録の保存に失敗しました: {}", "Failed to save the undo record: {}"),
+            HistoryReadFailed => ("実行履歴の読み込みに失敗しました: {}", "Failed to read the run history: {}"),
+            HistoryParseFailed => ("実行履歴の解析に失敗しました: {}", "Failed to parse the run history: {}"),
+            HistoryWriteFailed => ("実行履歴の保存に失敗しました: {}", "Failed to save the run history: {}"),
+            LastRunWriteFailed => ("前回の実行日時の保存に失敗しました: {}", "Failed to save the last run time: {}"),
+
+            ExtensionExcluded => ("拡張子 '{}' は対象外です", "Extension '{}' is not included"),
+            ContentTypeExcluded => ("ファイルの種類 '{}' は対象外です", "Content type '{}' is not included"),
+            ContentTypeUnavailable => ("ファイルの種類を判定できません: {}", "Failed to detect the content type: {}"),
+            UnknownContentType => ("不明", "unknown"),
+            SizeUnavailable => ("ファイルサイズを取得できません: {}", "Could not get the file size: {}"),
+            SizeBelowMin => ("サイズ {} バイトが最小サイズ {} バイト未満です", "Size {} bytes is below the minimum of {} bytes"),
+            SizeAboveMax => ("サイズ {} バイトが最大サイズ {} バイトを超えています", "Size {} bytes exceeds the maximum of {} bytes"),
+            ModifiedUnavailable => ("更新日時を取得できません: {}", "Could not get the modified time: {}"),
+            AgeBelowMin => ("経過日数 {} 日が最小日数 {} 日未満です", "Age of {} days is below the minimum of {} days"),
+            AgeAboveMax => ("経過日数 {} 日が最大日数 {} 日を超えています", "Age of {} days exceeds the maximum of {} days"),
+            NotModifiedSinceLastRun => ("前回の実行 ({}) 以降に更新されていません", "Not modified since the last run ({})"),
+
+            InvalidRenameResult => ("リネームテンプレートの展開結果が不正です ({}): '{}'", "Rename template produced an invalid path ({}): '{}'"),
+            MetadataFailed => ("メタデータの取得に失敗しました {}: {}", "Failed to read metadata {}: {}"),
+            FileTimeUnavailable => ("ファイルの日時を取得できません {}: {}", "Could not get the file time {}: {}"),
+            ModifiedTime => ("更新日時", "modified time"),
+            CreatedTime => ("作成日時", "creation time"),
+            InvalidDateFormat => ("日付フォーマットが無効です: {}", "Invalid date format: {}"),
+            InvalidDateFolder => ("日付フォーマットの結果がフォルダ名として不正です ({}): '{}'", "Date format produced an invalid folder name ({}): '{}'"),
+            FolderReadFailed => ("フォルダの読み取りに失敗しました: {}", "Failed to read the folder: {}"),
+            IgnoreFileReadFailed => ("{} の読み込みに失敗しました: {}", "Failed to read {}: {}"),
+            InvalidIgnorePattern => ("{} の glob が不正です '{}': {}", "Invalid glob in {} '{}': {}"),
+            EntryReadFailed => ("ファイルエントリの読み取りに失敗しました: {}", "Failed to read a directory entry: {}"),
+            FileTypeFailed => ("ファイルタイプの取得に失敗しました: {}", "Failed to get the file type: {}"),
+
+            MoveIntoItself => ("フォルダをそれ自身の中へ移動することはできません: {} -> {}", "Cannot move a folder into itself: {} -> {}"),
+            MoveFailed => ("移動失敗 {}: {}", "Failed to move {}: {}"),
+            CopyFailed => ("コピー失敗 {}: {}", "Failed to copy {}: {}"),
+            CopyMismatch => ("コピーの内容が一致しません。元ファイルは残しました: {}", "Copied content does not match; the original was kept: {}"),
+            VerifyFailed => ("コピーの検証に失敗しました {}: {}", "Failed to verify the copy {}: {}"),
+            RestoreTimesFailed => ("日時の復元に失敗しました {}: {}", "Failed to restore file times {}: {}"),
+            TrashFailed => ("ごみ箱へ移動できなかったため完全に削除します {}: {}", "Could not move to the trash, deleting permanently {}: {}"),
+            DeleteAfterCopyFailed => ("コピー後の削除失敗 {}: {}", "Failed to delete after copying {}: {}"),
+            SandboxCreateFailed => ("シミュレーション用の一時フォルダを作成できませんでした: {}", "Failed to create the temporary folder for the simulation: {}"),
+            SandboxReproduceFailed => ("シミュレーション用にフォルダを再現できませんでした {}: {}", "Failed to reproduce the folder for the simulation {}: {}"),
+            NoConfigFiles => ("設定ファイルが指定されていません", "No config files were given"),
+            DuplicateRuleAcrossFiles => ("ルール名 '{}' が {} と {} の両方にあります", "The rule name '{}' is used in both {} and {}"),
+            NoSchedule => ("設定に schedule がありません", "The config has no schedule"),
+            InvalidSchedule => ("schedule の cron 式が正しくありません '{}': {}", "Invalid cron expression in schedule '{}': {}"),
+            ScheduleNeverRuns => ("schedule '{}' が次に実行される日時がありません", "The schedule '{}' never runs again"),
+            ScheduledRun => ("=== スケジュール実行: {} ({}) ===", "=== Scheduled run: {} ({}) ==="),
+            FlattenIgnored => (
+                "ルール '{}': preserve_structure_from を指定しているため flatten は無視されます",
+                "Rule '{}': flatten is ignored because preserve_structure_from is set",
+            ),
+            NotUnderStructureBase => (
+                "{} は preserve_structure_from ({}) の外にあるためスキップしました",
+                "Skipped {} because it is outside preserve_structure_from ({})",
+            ),
+            HiddenSkipped => ("隠しファイルのためスキップ: {}", "Skipped hidden file: {}"),
+            EmptyDirRemoved => ("空のフォルダを削除: {}", "Removed empty folder: {}"),
+            EmptyDirRemoveFailed => ("空のフォルダを削除できませんでした {}: {}", "Failed to remove the empty folder {}: {}"),
+            EmptyDirScanFailed => ("空のフォルダを確認できませんでした {}: {}", "Failed to check for empty folders in {}: {}"),
+            PartialCopyRemoved => ("書きかけのコピーを削除しました: {}", "removed the incomplete copy: {}"),
+            PartialCopyRemoveFailed => (
+                "書きかけのコピーを削除できませんでした。手動で削除してください {}: {}",
+                "could not remove the incomplete copy, please delete it manually {}: {}",
+            ),
+            RetryInUse => ("使用中のため再試行します: {} ({}回目)", "File is in use, retrying: {} (attempt {})"),
+            SkippedInUse => ("使用中につきスキップ: {} ({})", "Skipped because the file is in use: {} ({})"),
+
+            NoLastConfig => (
+                "最後に使用した設定ファイルが見つかりません。まずは一度ルールを読み込んで実行してください。",
+                "No recently used config file was found. Load and run your rules once first.",
+            ),
+            BackupSourceMissing => ("バックアップ元のファイルが見つかりません: {}", "The file to back up was not found: {}"),
+            InvalidBackupTimestampFormat => (
+                "backup_timestamp_format '{}' はバックアップのファイル名に使えません (ファイル名に使えない文字を含むか、日時として読み取れません)",
+                "backup_timestamp_format '{}' cannot be used for backup file names (it contains characters not allowed in file names or cannot be read back as a date and time)",
+            ),
+            BackupFolderReadFailed => ("バックアップフォルダの読み取りに失敗しました: {}", "Failed to read the backup folder: {}"),
+            OldBackupRemoveFailed => ("古いバックアップの削除に失敗しました {}: {}", "Failed to delete an old backup {}: {}"),
+            BackupFailed => ("バックアップに失敗しました: {}", "Backup failed: {}"),
+            BackupDone => ("バックアップが完了しました。\n保存先: {}", "Backup completed.\nSaved to: {}"),
+            OldBackupsRemoved => ("\n古いバックアップを{}件削除しました", "\nDeleted {} old backup(s)"),
+            InvalidFolderSelected => ("無効なフォルダパスが選択されました。", "An invalid folder path was selected."),
+            BackupCancelled => ("バックアップ処理はキャンセルされました。", "Backup was cancelled."),
+            InvalidFileSelected => ("無効なファイルパスが選択されました。", "An invalid file path was selected."),
+            NotBackupFile => ("バックアップファイルではありません: {}", "Not a backup file: {}"),
+            RestoreConfirm => (
+                "次のバックアップで設定ファイルを上書きします。よろしいですか？\n復元元: {}\n復元先: {}",
+                "The config file will be overwritten with this backup. Continue?\nRestore from: {}\nRestore to: {}",
+            ),
+            RestoreTitle => ("ルールの復元", "Restore rules"),
+            RestoreCancelled => ("復元処理はキャンセルされました。", "Restore was cancelled."),
+            SafetyBackupFailed => ("現在の設定のバックアップに失敗しました: {}", "Failed to back up the current config: {}"),
+            SafetyBackupDone => ("現在の設定をバックアップしました: {}\n", "Backed up the current config: {}\n"),
+            RestoreFailed => ("復元に失敗しました: {}", "Restore failed: {}"),
+            RestoreDone => ("{}復元が完了しました。\n復元元: {}\n復元先: {}", "{}Restore completed.\nRestored from: {}\nRestored to: {}"),
+
+            SaveInvalidPatterns => ("無効な正規表現があるため保存できません:\n{}", "Cannot save because some regular expressions are invalid:\n{}"),
+            YamlSerializeFailed => ("YAMLへの変換に失敗しました: {}", "Failed to convert to YAML: {}"),
+            JsonSerializeFailed => ("JSONへの変換に失敗しました: {}", "Failed to convert to JSON: {}"),
+            TomlSerializeFailed => ("TOMLへの変換に失敗しました: {}", "Failed to convert to TOML: {}"),
+            ConfigBackupFailed => ("設定ファイルのバックアップに失敗しました: {}", "Failed to back up the config file: {}"),
+            ConfigSaveFailed => ("設定ファイルの保存に失敗しました: {}", "Failed to save the config file: {}"),
+
+            NoUndoRecord => ("取り消せる整理の記録がありません", "There is no run to undo"),
+            UndoRecordReadFailed => ("取り消し用の記録の読み込みに失敗しました: {}", "Failed to read the undo record: {}"),
+            UndoRecordParseFailed => ("取り消し用の記録の解析に失敗しました: {}", "Failed to parse the undo record: {}"),
+            UndoRecordRemoveFailed => ("取り消し用の記録の削除に失敗しました: {}", "Failed to delete the undo record: {}"),
+            UndoDestinationMissing => ("移動先のファイルが見つかりません: {}", "The moved file was not found: {}"),
+            UndoModified => ("移動後に変更されているためスキップしました: {}", "Skipped because it was modified after the move: {}"),
+            UndoOriginalExists => ("元の場所に同名のファイルが存在します: {}", "A file with the same name exists at the original location: {}"),
+            UndoFolderCreateFailed => ("元のフォルダを作成できません {}: {}", "Cannot create the original folder {}: {}"),
+            UndoRemoveFailed => ("作成したファイルを削除できません {}: {}", "Cannot remove the created file {}: {}"),
+            UndoExtractFailed => ("圧縮したファイルを展開できません {}: {}", "Cannot extract the compressed file {}: {}"),
+
+            NoWatchableFolders => ("監視できるソースフォルダがありません", "There are no source folders to watch"),
+            WatchStartFailed => ("フォルダ監視の開始に失敗しました: {}", "Failed to start watching folders: {}"),
+            WatchFolderFailed => ("フォルダを監視できません {}: {}", "Cannot watch the folder {}: {}"),
+            AutoOrganizeHeader => ("=== 自動整理: {} ===", "=== Automatic organizing: {} ==="),
+
+            PathMissing => ("パスが存在しません: {}", "The path does not exist: {}"),
+            FileManagerFailed => ("ファイルマネージャーを開けませんでした: {}", "Failed to open the file manager: {}"),
+            FolderSelectCancelled => ("フォルダ選択がキャンセルされました", "Folder selection was cancelled"),
+            ConfigFileFilter => ("設定ファイル", "Config files"),
+            PathConvertFailed => ("パスの変換に失敗しました", "Failed to convert the path"),
+            FileSelectCancelled => ("ファイル選択がキャンセルされました", "File selection was cancelled"),
+            MenuBackupRules => ("ルールのバックアップ", "Back up rules"),
+            MenuRestoreRules => ("バックアップから復元", "Restore from backup"),
+            ConfigDirNotFound => ("設定ディレクトリの取得に失敗しました", "Failed to locate the settings directory"),
+            LastConfigCreateFailed => ("設定ファイルの作成に失敗しました: {}", "Failed to create the settings file: {}"),
+            LastConfigWriteFailed => ("設定の保存に失敗しました: {}", "Failed to save the settings: {}"),
+            RecentConfigsReadFailed => ("最近使った設定ファイル一覧の読み込みに失敗しました: {}", "Failed to read the recent config list: {}"),
+            RecentConfigsSerializeFailed => ("最近使った設定ファイル一覧の変換に失敗しました: {}", "Failed to serialize the recent config list: {}"),
+            RecentConfigsWriteFailed => ("最近使った設定ファイル一覧の保存に失敗しました: {}", "Failed to save the recent config list: {}"),
+            RecentConfigsRemoveFailed => ("最近使った設定ファイルの記録の削除に失敗しました: {}", "Failed to delete the recent config list: {}"),
+        }
+    }
+}
+
+/// 現在の言語でのメッセージ
+pub fn text(msg: Msg) -> &'static str {
+    let (ja, en) = msg.templates();
+    match language() {
+        Language::Ja => ja,
+        Language::En => en,
+    }
+}
+
+/// 現在の言語のテンプレートの `{}` を引数で順に置き換える
+pub fn format(msg: Msg, args: &[&dyn Display]) -> String {
+    let mut parts = text(msg).split("{}");
+    let mut formatted = parts.next().unwrap_or_default().to_string();
+    let mut args = args.iter();
+    for part in parts {
+        if let Some(arg) = args.next() {
+            formatted.push_str(&arg.to_string());
+        }
+        formatted.push_str(part);
+    }
+    formatted
+}
+
+/// `tr!(Msg::X)` は &'static str、`tr!(Msg::X, a, b)` は引数を埋め込んだ String を返す
+macro_rules! tr {
+    ($msg:expr) => {
+        $crate::i18n::text($msg)
+    };
+    ($msg:expr, $($arg:expr),+ $(,)?) => {
+        $crate::i18n::format($msg, &[$(&$arg as &dyn std::fmt::Display),+])
+    };
+}
+pub(crate) use tr;
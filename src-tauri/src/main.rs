@@ -1,141 +1,5113 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 use std::fs;
-use std::path::Path;
-use regex::Regex;
+use std::path::{Path, PathBuf};
+use regex::{Captures, Regex, RegexBuilder};
 use serde::{Deserialize, Serialize};
-use tauri_plugin_dialog::DialogExt;
-use std::io::Write;
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+use tauri_plugin_shell::ShellExt;
+use std::io::{self, Read, Write};
 use dirs::config_dir;
-use tauri::{AppHandle, Manager};
-use chrono::Local;
+use tauri::{AppHandle, Emitter, Manager};
+use chrono::{DateTime, Local, NaiveDateTime};
+use filetime::FileTime;
 use tokio::sync::oneshot;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+use notify_debouncer_mini::notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult, DebouncedEvent, Debouncer};
 
-#[derive(Debug, Deserialize, Serialize)]
+mod i18n;
+use i18n::{tr, Language, Msg};
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
 struct OrganizeRule {
     name: String,
+    /// false の場合このルールは実行しない (未指定なら有効)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    enabled: Option<bool>,
+    /// ソースフォルダ。`Project_*` や `Project_{2021,2022}` のように書くと、マッチするすべてのフォルダを対象にする
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     source_folder: String,
+    /// 追加のソースフォルダ。source_folder と同じパターン・宛先で順に処理する
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    source_folders: Option<Vec<String>>,
+    /// match_target にマッチさせる正規表現。空ならすべてのファイルにマッチする
+    #[serde(default)]
     pattern: String,
+    /// 移動先フォルダ。`${client}` や `$1` で pattern のキャプチャを参照でき、ファイルごとに展開する
     destination_folder: String,
+    #[serde(default)]
+    on_conflict: ConflictMode,
+    /// on_conflict が keep_newer で宛先の方が新しい場合、スキップする古い元ファイルを削除する (コピー・リンクでは削除しない)
+    #[serde(default)]
+    delete_older_source: bool,
+    /// 移動するか、コピーするか、拡張子ごとのフォルダに移動するか (未指定なら移動)
+    #[serde(default)]
+    mode: RuleMode,
+    /// サブフォルダも再帰的に走査する。flatten でなければ移動先でもサブフォルダ構成を維持する
+    #[serde(default)]
+    recursive: bool,
+    /// 再帰走査の最大深さ (1 = 直下のサブフォルダまで)。未指定なら無制限
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_depth: Option<u32>,
+    /// サブフォルダ構成を再現せず、すべて宛先フォルダ直下に置く。同名のファイルには番号を付ける
+    #[serde(default)]
+    flatten: bool,
+    /// 宛先に再現するフォルダ構成の基準。ファイルのこのフォルダからの相対パスを宛先の下に作る (ソースフォルダより上も可)。
+    /// 基準の外にあるファイルは警告してスキップする
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    preserve_structure_from: Option<String>,
+    /// 移動先のファイル名テンプレート。`$1` や `${year}` で pattern のキャプチャを参照できる
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rename_template: Option<String>,
+    /// 更新日時から作るサブフォルダのフォーマット (例: "%Y/%m")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    date_subfolder: Option<String>,
+    /// 大文字・小文字を区別せずにマッチさせる
+    #[serde(default)]
+    case_insensitive: bool,
+    /// pattern と exclude を何に対して判定するか (未指定ならファイル名)
+    #[serde(default)]
+    match_target: MatchTarget,
+    /// pattern にマッチしても、いずれかにマッチしたファイルは対象外にする正規表現
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    exclude: Option<Vec<String>>,
+    /// 移動先のファイル名の整形 (rename_template の展開後に適用する)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sanitize: Option<SanitizeOptions>,
+    /// 対象とする拡張子 (大文字・小文字は区別しない)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    extensions: Option<Vec<String>>,
+    /// 対象とするファイルの種類 (例: "image/jpeg", "image/*")。拡張子ではなくファイル先頭の内容から判定する
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    content_type: Option<Vec<String>>,
+    /// 対象とする最小ファイルサイズ (バイト)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    min_size: Option<u64>,
+    /// 対象とする最大ファイルサイズ (バイト)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_size: Option<u64>,
+    /// 更新から指定日数以上経過したファイルのみ対象にする
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    min_age_days: Option<u64>,
+    /// 更新から指定日数以内のファイルのみ対象にする
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_age_days: Option<u64>,
+    /// 名前が pattern にマッチするフォルダもフォルダごと移動する (拡張子・サイズ等のフィルタは適用しない)
+    #[serde(default)]
+    include_directories: bool,
+    /// 名前が `.` で始まるファイル・フォルダ (Windows では隠し属性のもの) も対象にする
+    #[serde(default)]
+    include_hidden: bool,
+    /// copy+delete で移動する際、元ファイルを完全削除せずごみ箱へ送る
+    #[serde(default)]
+    use_trash: bool,
+    /// copy+delete で移動する際、ハッシュでコピー内容を検証してから元ファイルを削除する
+    #[serde(default)]
+    verify: bool,
+    /// 移動したファイルの隣に、ルール名・移動元・日時を記録した `<ファイル名>.meta.json` を書く
+    #[serde(default)]
+    write_sidecar: bool,
+    /// 使用中のファイルの移動を再試行する回数 (未指定なら再試行せずスキップする)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    retry_count: Option<u32>,
+    /// 再試行までの待ち時間 (ミリ秒)。未指定なら 1000
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    retry_delay_ms: Option<u64>,
+    /// 宛先フォルダが無ければ作成する。false の場合は警告してルールをスキップする
+    #[serde(default = "default_true", skip_serializing_if = "is_true")]
+    create_destination: bool,
+    /// 実行順の優先度。大きいルールから実行し、未指定は 0 として扱う
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    priority: Option<i32>,
+    /// 宛先がネットワーク上 (UNC パス) の場合に、到達できるか確認するときの待ち時間 (ミリ秒)。未指定なら 3000
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    network_timeout_ms: Option<u64>,
+    /// ソースフォルダに最低限残すファイル数。これを下回る移動は行わず、残りはそのままにする
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    keep_at_least: Option<usize>,
+    /// 同時に実行する転送の数。未指定なら 1 件ずつ転送する (遅いネットワークドライブでは小さくする)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_concurrency: Option<usize>,
+    /// ルールの実行後、ソースフォルダの下の空になったサブフォルダを削除する (ソースフォルダ自体は残す)
+    #[serde(default)]
+    remove_empty_dirs: bool,
+    /// remove_empty_dirs で、隠しファイルやシステムファイル (`Thumbs.db` など) しか無いフォルダも空とみなして削除する
+    #[serde(default)]
+    remove_empty_dirs_with_hidden: bool,
+    /// 宛先に圧縮して置く (move と copy のみ)。元ファイルは圧縮ファイルを閉じた後に削除し、取り消しの対象にしない
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    compress: Option<CompressMode>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn is_true(value: &bool) -> bool {
+    *value
+}
+
+impl OrganizeRule {
+    /// source_folder と source_folders を合わせたソースフォルダ (空の source_folder は除く)
+    fn sources(&self) -> impl Iterator<Item = &String> {
+        std::iter::once(&self.source_folder)
+            .filter(|folder| !folder.is_empty())
+            .chain(self.source_folders.iter().flatten())
+    }
+
+    fn sources_mut(&mut self) -> impl Iterator<Item = &mut String> {
+        std::iter::once(&mut self.source_folder)
+            .filter(|folder| !folder.is_empty())
+            .chain(self.source_folders.iter_mut().flatten())
+    }
+
+    /// destination_folder のうち、キャプチャのプレースホルダ (`$`) を含まない先頭のフォルダ
+    fn destination_root(&self) -> PathBuf {
+        Path::new(&self.destination_folder).components()
+            .take_while(|component| !component.as_os_str().to_string_lossy().contains('$'))
+            .collect()
+    }
+}
+
+/// 移動先のファイル名の整形方法。各項目は個別に有効にできる
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+struct SanitizeOptions {
+    /// 小文字に変換する
+    #[serde(default)]
+    lowercase: bool,
+    /// 空白をアンダースコアに置き換える
+    #[serde(default)]
+    replace_spaces: bool,
+    /// Windows でファイル名に使えない文字 (`<>:"/\|?*`) を取り除く
+    #[serde(default)]
+    strip_illegal: bool,
+}
+
+/// ファイルの処理方法
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+enum RuleMode {
+    #[default]
+    Move,
+    /// コピーのみ行い、元ファイルは残す
+    Copy,
+    /// 宛先フォルダの下の拡張子名のフォルダ (例: `pdf/`) に移動する。拡張子がなければ `_noext/`
+    ByExtension,
+    /// 宛先フォルダの下のファイル名の先頭の文字 (大文字) のフォルダ (例: `A/`) に移動する。文字以外で始まる場合は `#/`
+    Alphabetical,
+    /// 元ファイルは残し、宛先に元ファイルを指すシンボリックリンクを作る
+    Symlink,
+    /// 元ファイルは残し、宛先にハードリンクを作る (ソースと宛先が同じボリュームにある場合のみ)
+    Hardlink,
+}
+
+impl RuleMode {
+    /// 元ファイルをソースフォルダに残すか (コピーとリンクは取り消しや重複の削除の対象にしない)
+    fn keeps_source(self) -> bool {
+        matches!(self, RuleMode::Copy | RuleMode::Symlink | RuleMode::Hardlink)
+    }
+}
+
+/// 宛先に置くときの圧縮形式
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum CompressMode {
+    /// ファイルごとに `name.gz` にする
+    Gzip,
+    /// ルールでマッチしたファイルをまとめて 1 つの `ルール名_日時.zip` にする
+    Zip,
+}
+
+/// パターンを照合する対象
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+enum MatchTarget {
+    #[default]
+    Filename,
+    /// ソースフォルダからの相対パス (例: "2024/report.pdf")
+    Path,
+    /// 絶対パス
+    Fullpath,
+}
+
+/// 宛先に同名ファイルが既に存在する場合の挙動
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+enum ConflictMode {
+    Overwrite,
+    Skip,
+    #[default]
+    Rename,
+    /// 内容が同じなら重複として元ファイルを削除し、異なれば rename と同じく番号を付ける
+    Dedupe,
+    /// 更新日時を比べ、元ファイルの方が新しければ上書きし、宛先の方が新しければ (同じなら) スキップする
+    #[serde(rename = "keep_newer")]
+    KeepNewer,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Config {
+    rules: Vec<OrganizeRule>,
+    /// ログの出力先。未指定ならアプリの設定ディレクトリの file-organizer.log
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    log_file: Option<String>,
+    /// false の場合ログを出力しない (未指定なら出力する)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    logging: Option<bool>,
+    /// ログの形式。"json" にすると 1 行 1 レコードの JSON Lines で出力する
+    #[serde(default)]
+    log_format: LogFormat,
+    /// ログファイルがこのサイズ (バイト) を超えたらローテーションする。未指定なら 5MB
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_log_bytes: Option<u64>,
+    /// ローテーションで残す古いログの数。未指定なら 3
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    log_keep: Option<usize>,
+    /// ルールのバックアップを保存先フォルダに残す最大件数。未指定なら削除しない
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    backup_keep: Option<usize>,
+    /// バックアップのファイル名に付ける日時の形式 (chrono の strftime)。未指定なら `%Y年%m月%d日%H時%M分%S秒`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    backup_timestamp_format: Option<String>,
+    /// 結果・エラーメッセージの表示言語 ("ja" / "en")。未指定なら日本語
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    language: Option<Language>,
+    /// 複数のルールにマッチするファイルは、ルール順で最初のルールだけが扱う
+    #[serde(default)]
+    first_match_wins: bool,
+    /// エラーが 1 件でもあれば、その実行で移動したファイルをすべて元に戻してエラーを返す。
+    /// 重複として削除したファイルとコピーは戻さない
+    #[serde(default)]
+    transactional: bool,
+    /// 1 つのルールでこの件数を超えるファイルを移動する場合、実行前に確認する。
+    /// 監視による自動整理では確認を求めず、そのルールを実行しない
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    confirm_threshold: Option<usize>,
+    /// confirm_threshold の確認を省略して実行する (ヘッドレス実行やテスト用)
+    #[serde(default)]
+    auto_confirm: bool,
+    /// 実行履歴に残す最大件数。未指定なら 200
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    history_keep: Option<usize>,
+    /// 前回エラーなく完了した実行以降に更新されたファイルだけを対象にする。
+    /// 監視による自動整理にも適用するが、自動整理の実行日時は記録しない
+    #[serde(default)]
+    incremental: bool,
+    /// 隠しファイルとして対象外にしたものなど、詳細な経過もログに残す
+    #[serde(default)]
+    verbose_log: bool,
+    /// 整理がエラーなく完了した後に実行するコマンド (ドライランでは実行しない)。
+    /// 集計は FILE_ORGANIZER_* の環境変数で渡す
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    post_run_command: Option<String>,
+    /// start_schedule で自動実行する日時の cron 式 (「分 時 日 月 曜日」。秒から始まる 6 項目も可)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    schedule: Option<String>,
+    /// すべてのルールを実行した後、どのルールにもマッチしなかったソースフォルダ内のファイルを移動する先
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    unmatched_destination: Option<String>,
+    /// 1 回の実行で移動するファイル数の上限。達したら残りのファイルは処理しない
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_files_per_run: Option<usize>,
+    /// 1 回の実行で移動する合計サイズ (バイト) の上限。超える前に残りのファイルの処理をやめる
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_total_bytes_per_run: Option<u64>,
+}
+
+impl Config {
+    /// 実行中のメッセージに使う言語
+    fn language(&self) -> Language {
+        self.language.unwrap_or_default()
+    }
+
+    /// priority の高い順にルールを並べ替える。同じ priority (未指定は 0) は設定ファイルの順を保つ
+    fn sort_rules_by_priority(&mut self) {
+        self.rules.sort_by_key(|rule| std::cmp::Reverse(rule.priority.unwrap_or(0)));
+    }
+
+    fn run_limits(&self) -> RunLimits {
+        RunLimits {
+            max_files: self.max_files_per_run,
+            max_bytes: self.max_total_bytes_per_run,
+            ..RunLimits::default()
+        }
+    }
+}
+
+/// ログの出力形式
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+enum LogFormat {
+    /// 人が読むための文章形式
+    #[default]
+    Text,
+    /// ファイル単位のイベントを JSON Lines で記録する
+    Json,
+}
+
+/// JSON Lines 形式のログの 1 レコード
+#[derive(Serialize, Default)]
+struct LogRecord {
+    ts: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rule: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    file: Option<String>,
+    action: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    from: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    to: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    strategy: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dry_run: Option<bool>,
+}
+
+impl LogRecord {
+    fn new(action: &'static str) -> Self {
+        LogRecord {
+            ts: Local::now().to_rfc3339(),
+            action,
+            ..Default::default()
+        }
+    }
+
+    fn rule(mut self, name: &str) -> Self {
+        self.rule = Some(name.to_string());
+        self
+    }
+
+    /// 対象ファイル。file にはファイル名、from / to にはフルパスを入れる
+    fn paths(mut self, from: &Path, to: Option<&Path>) -> Self {
+        self.file = from.file_name().map(|name| name.to_string_lossy().into_owned());
+        self.from = Some(from.to_string_lossy().into_owned());
+        self.to = to.map(|to| to.to_string_lossy().into_owned());
+        self
+    }
+
+    fn message(mut self, message: impl Into<String>) -> Self {
+        self.message = Some(message.into());
+        self
+    }
+
+    fn write(&self, out: &mut impl Write) {
+        if let Ok(line) = serde_json::to_string(self) {
+            writeln!(out, "{}", line).ok();
+        }
+    }
+}
+
+/// 整理対象として走査されたファイル
+struct SourceFile {
+    path: PathBuf,
+    /// フォルダごと移動する対象 (include_directories 有効時のみ)
+    is_dir: bool,
+    file_name: std::ffi::OsString,
+    /// source_folder からの相対パス
+    relative_path: PathBuf,
+}
+
+/// move_file の動作オプション
+#[derive(Debug, Default, Clone)]
+struct MoveOptions {
+    use_trash: bool,
+    verify: bool,
+    /// LARGE_FILE_THRESHOLD を超えるファイルのコピー中に進捗を送る先
+    progress: Option<CopyProgress>,
+}
+
+impl MoveOptions {
+    fn for_rule(rule: &OrganizeRule) -> Self {
+        MoveOptions {
+            use_trash: rule.use_trash,
+            verify: rule.verify,
+            progress: None,
+        }
+    }
+}
+
+/// 大きなファイルのコピー中に organize-file-progress イベントを送る先
+#[derive(Debug, Clone)]
+struct CopyProgress {
+    app_handle: AppHandle,
+    rule_name: String,
+}
+
+impl CopyProgress {
+    fn emit(&self, file: &Path, copied: u64, total: u64) {
+        let progress = FileProgress {
+            rule_name: self.rule_name.clone(),
+            file: file.to_string_lossy().into_owned(),
+            copied,
+            total,
+        };
+        self.app_handle.emit("organize-file-progress", progress).ok();
+    }
+}
+
+/// move_file の結果
+struct MoveOutcome {
+    /// 移動方式 ("rename" / "copy+delete" / "copy+trash")
+    strategy: &'static str,
+    /// 移動は成功したが利用者に知らせるべき事項
+    warnings: Vec<String>,
+}
+
+/// move_file の失敗
+#[derive(Debug)]
+struct MoveError {
+    message: String,
+    /// 他のプログラムが使用中のため失敗した (時間をおけば成功する可能性がある)
+    in_use: bool,
+}
+
+impl MoveError {
+    fn new(message: String) -> Self {
+        MoveError { message, in_use: false }
+    }
+
+    fn io(message: String, e: &io::Error) -> Self {
+        MoveError { message, in_use: is_file_in_use_error(e) }
+    }
+}
+
+/// 整理処理の実行状態 (Tauri の State として共有)
+#[derive(Default)]
+struct OrganizeState {
+    cancel_requested: AtomicBool,
+    /// この件数を超えて移動するルールは、実行前にフロントエンドの確認を待つ。None なら確認しない
+    confirm_threshold: Mutex<Option<usize>>,
+    /// 確認を求めた要求の ID と、confirm_organize の回答を渡す送信側。
+    /// 同じルールを別の実行が同時に確認することもあるため、ルール名ではなく要求ごとの ID で区別する
+    pending_confirmations: Mutex<std::collections::HashMap<u64, std::sync::mpsc::Sender<bool>>>,
+    /// 次に確認を求める要求の ID
+    next_confirmation: AtomicU64,
+    /// incremental で、これ以前に更新されたファイルを対象外にする日時
+    modified_since: Mutex<Option<SystemTime>>,
+    /// 監視やスケジュールによる自動整理の実行か。確認に答える人がいるとは限らないため、confirm_threshold を超えるルールは実行しない
+    unattended: AtomicBool,
+    /// verbose_log が有効か
+    verbose_log: AtomicBool,
+    /// max_files_per_run / max_total_bytes_per_run と、この実行で移動した件数・サイズ
+    run_limits: Mutex<RunLimits>,
+    /// 最後に実行した整理の結果 (export_results で結果を省略したときに使う)
+    last_results: Mutex<Vec<OrganizeResult>>,
+}
+
+/// 1 回の実行で移動するファイルの上限と、ここまでに移動した量 (並列に実行するルール全体で数える)
+#[derive(Debug, Default)]
+struct RunLimits {
+    max_files: Option<usize>,
+    max_bytes: Option<u64>,
+    files: usize,
+    bytes: u64,
+}
+
+impl RunLimits {
+    /// 次に `size` バイトのファイルを移動しても上限を超えなければ、その分を数えて true を返す。
+    /// 並列に実行するルールが同時に上限の手前で確認しても超えないよう、確認と記録を 1 回のロックで行う
+    fn try_reserve(&mut self, size: u64) -> bool {
+        if self.max_files.is_some_and(|max| self.files >= max)
+            || self.max_bytes.is_some_and(|max| self.bytes + size > max)
+        {
+            return false;
+        }
+        self.files += 1;
+        self.bytes += size;
+        true
+    }
+
+    /// try_reserve で数えたファイルを移動できなかったときに戻す
+    fn release(&mut self, size: u64) {
+        self.files = self.files.saturating_sub(1);
+        self.bytes = self.bytes.saturating_sub(size);
+    }
+}
+
+/// 確認を求めるときに送るイベントの内容
+#[derive(Debug, Clone, Serialize)]
+struct ConfirmRequest {
+    /// confirm_organize で回答するときに渡す ID
+    id: u64,
+    rule_name: String,
+    count: usize,
+}
+
+/// 監視モードの状態 (Tauri の State として共有)。Debouncer を破棄すると監視が止まる
+#[derive(Default)]
+struct WatchState {
+    debouncer: Mutex<Option<Debouncer<RecommendedWatcher>>>,
+}
+
+/// スケジュール実行の状態 (Tauri の State として共有)
+#[derive(Default)]
+struct ScheduleState {
+    /// 実行中のスケジュールの待機を止める通知
+    stop: Mutex<Option<std::sync::Arc<tokio::sync::Notify>>>,
+    /// スケジュール実行の間保持する。置き換え前のスケジュールの実行と重ならないようにする
+    running: std::sync::Arc<tokio::sync::Mutex<()>>,
+}
+
+/// scheduled-run イベントのペイロード
+#[derive(Debug, Clone, Serialize)]
+struct ScheduledRun {
+    config_path: String,
+    started_at: String,
+    moved: usize,
+    /// 整理を実行できなかった場合のエラー
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    /// 次に実行する日時
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next_run: Option<String>,
+}
+
+/// 使用中のファイルを再試行するまでの既定の待ち時間 (ミリ秒)
+const DEFAULT_RETRY_DELAY_MS: u64 = 1000;
+
+/// これより大きいファイルは分割してコピーし、ファイル単位の進捗を送る
+const LARGE_FILE_THRESHOLD: u64 = 100 * 1024 * 1024;
+
+/// 分割コピーで一度に読み書きする大きさ
+const COPY_CHUNK_SIZE: usize = 1024 * 1024;
+
+/// 分割コピーで進捗を送る間隔 (バイト)
+const COPY_PROGRESS_INTERVAL: u64 = 16 * 1024 * 1024;
+
+/// ネットワーク上の宛先に到達できるか確認するときの既定の待ち時間 (ミリ秒)
+const DEFAULT_NETWORK_TIMEOUT_MS: u64 = 3000;
+
+/// 書き込み中のファイルを移動しないよう、変更が止まってから処理するまでの待ち時間
+const WATCH_DEBOUNCE: Duration = Duration::from_secs(2);
+
+/// organize-progress イベントのペイロード
+#[derive(Debug, Clone, Serialize)]
+struct OrganizeProgress {
+    rule_name: String,
+    processed: usize,
+    total: usize,
+    current_file: String,
+}
+
+/// organize-file-progress イベントのペイロード (1 ファイルのコピーの進み具合)
+#[derive(Debug, Clone, Serialize)]
+struct FileProgress {
+    rule_name: String,
+    file: String,
+    /// コピー済みのバイト数
+    copied: u64,
+    total: u64,
+}
+
+/// 移動 (またはドライランで移動予定) となったファイル
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct MovedFile {
+    source: String,
+    destination: String,
+    /// 移動方式 ("rename" / "copy+delete")。ドライランでは "dry-run"
+    strategy: String,
+    /// ファイルのサイズ (バイト)
+    size: u64,
+    /// 移動 (ドライランでは判定) した日時 (RFC 3339)
+    timestamp: String,
+}
+
+impl MovedFile {
+    fn new(source: &Path, destination: &Path, strategy: &str, size: u64) -> Self {
+        MovedFile {
+            source: source.to_string_lossy().into_owned(),
+            destination: destination.to_string_lossy().into_owned(),
+            strategy: strategy.to_string(),
+            size,
+            timestamp: Local::now().to_rfc3339(),
+        }
+    }
+}
+
+/// ファイルを移動しなかった理由
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum SkipReason {
+    /// pattern にマッチしない
+    NoMatch,
+    /// exclude、extensions または content_type で除外された
+    Excluded,
+    /// min_size / max_size の範囲外、またはサイズを取得できなかった
+    FilterSize,
+    /// min_age_days / max_age_days、または incremental で前回から更新されていない
+    FilterAge,
+    /// 宛先に同名のファイルがあり、on_conflict によりスキップした
+    Conflict,
+    /// 他のプログラムが使用中だった
+    Locked,
+    /// 隠しファイル・フォルダで、include_hidden が無効だった
+    Hidden,
+}
+
+/// 移動しなかったファイルとその理由
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SkippedFile {
+    path: String,
+    reason: SkipReason,
+    /// 理由の詳細 (フィルタの値や競合の内容)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    message: Option<String>,
+}
+
+impl SkippedFile {
+    fn new(path: &Path, reason: SkipReason, message: Option<String>) -> Self {
+        SkippedFile {
+            path: path.to_string_lossy().into_owned(),
+            reason,
+            message,
+        }
+    }
+}
+
+/// 圧縮して書き込んだファイル (gzip では 1 ファイルごと、zip ではルールごとに 1 つ)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CompressedArchive {
+    path: String,
+    files: usize,
+    /// 圧縮前の合計サイズ (バイト)
+    original_size: u64,
+    /// 圧縮後のサイズ (バイト)
+    compressed_size: u64,
+    /// 圧縮後のサイズ / 圧縮前のサイズ
+    ratio: f64,
+}
+
+impl CompressedArchive {
+    fn new(path: &Path, files: usize, original_size: u64, compressed_size: u64) -> Self {
+        CompressedArchive {
+            path: path.to_string_lossy().into_owned(),
+            files,
+            original_size,
+            compressed_size,
+            ratio: if original_size == 0 { 1.0 } else { compressed_size as f64 / original_size as f64 },
+        }
+    }
+}
+
+/// write_sidecar で移動先の隣に書くメタデータ
+#[derive(Debug, Serialize, Deserialize)]
+struct Sidecar {
+    rule_name: String,
+    /// 移動元のパス
+    source: String,
+    /// 移動方式 ("rename" / "copy+delete" など)
+    strategy: String,
+    /// 移動した日時 (RFC 3339)
+    timestamp: String,
+}
+
+/// 取り消し用に記録する 1 件の移動、または作成したファイル
+#[derive(Debug, Serialize, Deserialize)]
+struct JournalEntry {
+    source: String,
+    destination: String,
+    /// 移動直後の移動先ファイルのサイズと更新日時 (取り消し時に変更の有無を判定する)
+    size: u64,
+    modified: Option<std::time::SystemTime>,
+    #[serde(default)]
+    action: JournalAction,
+}
+
+/// 取り消すときの戻し方
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum JournalAction {
+    /// destination を source へ移動して戻す
+    #[default]
+    Move,
+    /// 元ファイルが残っているため、作成した destination (コピー・リンク・アーカイブ) を削除する
+    Remove,
+    /// gzip で圧縮した destination を source に展開し、destination を削除する
+    Gunzip,
+    /// zip アーカイブの destination から entry を source に取り出す。
+    /// アーカイブ自体は、先に記録した Remove ですべて取り出せた場合だけ削除する
+    Unzip { entry: String },
+}
+
+/// undo_last_organize の結果
+#[derive(Debug, Serialize)]
+struct UndoResult {
+    restored: Vec<MovedFile>,
+    /// 削除した、整理で作成したファイル
+    removed: Vec<String>,
+    skipped: Vec<String>,
+}
+
+/// ルールごとの整理結果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct OrganizeResult {
+    rule_name: String,
+    dry_run: bool,
+    moved: Vec<MovedFile>,
+    /// 移動 (ドライランでは移動予定) したファイルの合計サイズ (バイト)
+    bytes: u64,
+    /// compress で書き込んだ圧縮ファイル
+    #[serde(default)]
+    archives: Vec<CompressedArchive>,
+    /// 移動しなかったソースフォルダ内のファイルと理由
+    #[serde(default)]
+    skipped: Vec<SkippedFile>,
+    warnings: Vec<String>,
+    errors: Vec<String>,
+}
+
+/// ルールごとの集計
+#[derive(Debug, Serialize)]
+struct RuleSummary {
+    rule_name: String,
+    moved: usize,
+    bytes: u64,
+}
+
+/// 整理全体の集計
+#[derive(Debug, Serialize)]
+struct OrganizeSummary {
+    total_moved: usize,
+    total_bytes: u64,
+    rules: Vec<RuleSummary>,
+    elapsed_ms: u64,
+}
+
+impl OrganizeSummary {
+    fn from_results(results: &[OrganizeResult], elapsed: Duration) -> Self {
+        OrganizeSummary {
+            total_moved: results.iter().map(|result| result.moved.len()).sum(),
+            total_bytes: results.iter().map(|result| result.bytes).sum(),
+            rules: results.iter()
+                .map(|result| RuleSummary {
+                    rule_name: result.rule_name.clone(),
+                    moved: result.moved.len(),
+                    bytes: result.bytes,
+                })
+                .collect(),
+            elapsed_ms: elapsed.as_millis() as u64,
+        }
+    }
+}
+
+/// organize_files の戻り値
+#[derive(Debug, Serialize)]
+struct OrganizeReport {
+    results: Vec<OrganizeResult>,
+    summary: OrganizeSummary,
+    /// 実際にルールを実行した順 (priority の降順、同じ priority は設定ファイルの順)
+    rule_order: Vec<String>,
+    /// post_run_command を実行した場合の結果。失敗しても整理の結果には影響しない
+    #[serde(skip_serializing_if = "Option::is_none")]
+    post_run: Option<PostRunResult>,
+    /// ドライランで、実際に実行した場合のおおよその所要時間 (秒)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    estimated_seconds: Option<f64>,
+}
+
+/// post_run_command の実行結果
+#[derive(Debug, Serialize)]
+struct PostRunResult {
+    command: String,
+    success: bool,
+    exit_code: Option<i32>,
+    stdout: String,
+    stderr: String,
+    /// コマンドを起動できなかった場合のエラー
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Severity {
+    Error,
+    Warning,
+}
+
+/// validate_config が検出した問題
+#[derive(Debug, Serialize)]
+struct ValidationIssue {
+    rule_name: String,
+    severity: Severity,
+    message: String,
+}
+
+/// test_pattern のサンプルごとの結果
+#[derive(Debug, Serialize)]
+struct PatternTestResult {
+    name: String,
+    matched: bool,
+    /// 番号付きキャプチャ ($1, $2, ...)。マッチしなかったグループは null
+    captures: Vec<Option<String>>,
+    /// 名前付きキャプチャ
+    named_captures: std::collections::BTreeMap<String, String>,
+}
+
+/// describe_pattern の結果
+#[derive(Debug, Serialize)]
+struct PatternDescription {
+    /// キャプチャグループの数 (全体のマッチ `$0` は含まない)
+    group_count: usize,
+    /// 名前付きグループの名前 (出現順)
+    group_names: Vec<String>,
+}
+
+/// 設定ファイルの形式
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ConfigFormat {
+    Yaml,
+    Json,
+    Toml,
+}
+
+impl ConfigFormat {
+    /// 拡張子から形式を判定する。判定できない場合は従来どおり YAML とみなす
+    fn from_path(path: &Path) -> Self {
+        match path.extension().map(|e| e.to_string_lossy().to_lowercase()).as_deref() {
+            Some("json") => ConfigFormat::Json,
+            Some("toml") => ConfigFormat::Toml,
+            _ => ConfigFormat::Yaml,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct LoadResult {
+    path: String,
+    config: Config,
+}
+
+/// 画面で設定ファイルを読み込む。ダイアログなど設定に結び付かない表示もその設定の language にする
+#[tauri::command]
+async fn load_config(config_path: String) -> Result<Config, String> {
+    let config = read_config(&config_path)?;
+    i18n::set_default_language(config.language());
+    Ok(config)
+}
+
+/// 設定ファイルを読み込む。表示言語は変えないため、実行には use_language で config.language() を使う
+fn read_config(config_path: &str) -> Result<Config, String> {
+    let content = fs::read_to_string(config_path)
+        .map_err(|e| tr!(Msg::ConfigReadFailed, e))?;
+    
+    let config: Config = match ConfigFormat::from_path(Path::new(config_path)) {
+        ConfigFormat::Yaml => serde_yaml::from_str(&content)
+            .map_err(|e| tr!(Msg::YamlParseFailed, e))?,
+        ConfigFormat::Json => serde_json::from_str(&content)
+            .map_err(|e| tr!(Msg::JsonParseFailed, e))?,
+        ConfigFormat::Toml => toml::from_str(&content)
+            .map_err(|e| tr!(Msg::TomlParseFailed, e))?,
+    };
+    
+    Ok(config)
+}
+
+/// 複数の設定ファイルのルールを指定した順につなげた設定を返す。ルール以外の項目は最初のファイルのものを使う。
+/// 別のファイルと同じ名前のルールがあれば、すべて列挙したエラーを返す
+#[tauri::command]
+async fn load_configs(config_paths: Vec<String>) -> Result<Config, String> {
+    let mut paths = config_paths.into_iter();
+    let first = paths.next().ok_or_else(|| tr!(Msg::NoConfigFiles).to_string())?;
+    let mut config = read_config(&first)
+        .map_err(|e| format!("{}: {}", first, e))?;
+    let mut owners: std::collections::HashMap<String, String> = config.rules.iter()
+        .map(|rule| (rule.name.clone(), first.clone()))
+        .collect();
+    let mut duplicates = Vec::new();
+    for path in paths {
+        let other = read_config(&path)
+            .map_err(|e| format!("{}: {}", path, e))?;
+        for rule in other.rules {
+            match owners.get(&rule.name) {
+                Some(owner) if owner != &path => duplicates.push(tr!(Msg::DuplicateRuleAcrossFiles, rule.name, owner, path)),
+                Some(_) => {}
+                None => {
+                    owners.insert(rule.name.clone(), path.clone());
+                }
+            }
+            config.rules.push(rule);
+        }
+    }
+    // ルール以外と同じく、最初のファイルの language を使う
+    i18n::set_default_language(config.language());
+    if !duplicates.is_empty() {
+        return Err(duplicates.join("\n"));
+    }
+    Ok(config)
+}
+
+/// 設定をファイルに書き戻す。既存ファイルは上書き前にタイムスタンプ付きの .bak として残す
+#[tauri::command]
+async fn save_config(config_path: String, config: Config) -> Result<(), String> {
+    let invalid: Vec<String> = config.rules.iter()
+        .flat_map(|rule| {
+            CompiledRule::new(rule).err().into_iter().flatten().map(|e| format!("{}: {}", rule.name, e))
+        })
+        .collect();
+    if !invalid.is_empty() {
+        return Err(tr!(Msg::SaveInvalidPatterns, invalid.join("\n")));
+    }
+
+    let path = Path::new(&config_path);
+    let content = match ConfigFormat::from_path(path) {
+        ConfigFormat::Yaml => serde_yaml::to_string(&config)
+            .map_err(|e| tr!(Msg::YamlSerializeFailed, e))?,
+        ConfigFormat::Json => serde_json::to_string_pretty(&config)
+            .map_err(|e| tr!(Msg::JsonSerializeFailed, e))?,
+        ConfigFormat::Toml => toml::to_string_pretty(&config)
+            .map_err(|e| tr!(Msg::TomlSerializeFailed, e))?,
+    };
+
+    if path.exists() {
+        let timestamp = Local::now().format("%Y%m%d%H%M%S");
+        let file_name = path.file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "rules.yaml".to_string());
+        let backup_path = path.with_file_name(format!("{}.{}.bak", file_name, timestamp));
+        fs::copy(path, &backup_path)
+            .map_err(|e| tr!(Msg::ConfigBackupFailed, e))?;
+    }
+    fs::write(path, content)
+        .map_err(|e| tr!(Msg::ConfigSaveFailed, e))
+}
+
+/// `index` 番目のルールを複製して直後に挿入した設定を返す (保存はしない)。
+/// 複製の名前は「名前 (コピー)」とし、既存の名前と重なる場合は「名前 (コピー 2)」のように番号を付ける
+#[tauri::command]
+async fn duplicate_rule(mut config: Config, index: usize) -> Result<Config, String> {
+    let mut rule = config.rules.get(index)
+        .cloned()
+        .ok_or_else(|| tr!(Msg::RuleIndexOutOfRange, index))?;
+    let mut name = format!("{} ({})", rule.name, tr!(Msg::CopySuffix));
+    let mut counter = 2;
+    while config.rules.iter().any(|existing| existing.name == name) {
+        name = format!("{} ({} {})", rule.name, tr!(Msg::CopySuffix), counter);
+        counter += 1;
+    }
+    rule.name = name;
+    config.rules.insert(index + 1, rule);
+    Ok(config)
+}
+
+/// `old_name` のルールの名前を `new_name` に変えた設定を返す (保存はしない)。
+/// 名前が空の場合と、他のルールと重なる場合はエラーにする
+#[tauri::command]
+async fn rename_rule(mut config: Config, old_name: String, new_name: String) -> Result<Config, String> {
+    let new_name = new_name.trim().to_string();
+    if new_name.is_empty() {
+        return Err(tr!(Msg::RuleNameEmpty).to_string());
+    }
+    if new_name != old_name && config.rules.iter().any(|rule| rule.name == new_name) {
+        return Err(tr!(Msg::RuleNameTaken, new_name));
+    }
+    let rule = config.rules.iter_mut()
+        .find(|rule| rule.name == old_name)
+        .ok_or_else(|| tr!(Msg::RuleNotFound, old_name))?;
+    rule.name = new_name;
+    Ok(config)
+}
+
+/// suggest_rules で接頭辞のルールを作るのに必要な、同じ接頭辞を持つファイルの数
+const SUGGEST_MIN_GROUP: usize = 3;
+
+/// suggest_rules で拡張子をまとめる分類 (フォルダ名のメッセージと拡張子)
+const SUGGEST_CATEGORIES: &[(Msg, &[&str])] = &[
+    (Msg::CategoryImages, &["jpg", "jpeg", "png", "gif", "bmp", "webp", "heic", "svg"]),
+    (Msg::CategoryDocuments, &["pdf", "doc", "docx", "xls", "xlsx", "ppt", "pptx", "txt", "md", "csv"]),
+    (Msg::CategoryVideos, &["mp4", "mov", "avi", "mkv", "wmv", "webm"]),
+    (Msg::CategoryMusic, &["mp3", "wav", "flac", "aac", "m4a", "ogg"]),
+    (Msg::CategoryArchives, &["zip", "rar", "7z", "tar", "gz"]),
+    (Msg::CategoryInstallers, &["exe", "msi", "dmg", "pkg"]),
+];
+
+/// `folder` の直下のファイルを見て、ルールの案を返す (保存はしない)。
+/// 同じ接頭辞 (`IMG_` など) のファイルが SUGGEST_MIN_GROUP 件以上あればその接頭辞のルールを先に置き、
+/// 残りは拡張子の分類ごとのルールにする。分類にない拡張子は 2 件以上あればその拡張子のフォルダにする
+#[tauri::command]
+async fn suggest_rules(folder: String) -> Result<Vec<OrganizeRule>, String> {
+    let root = Path::new(&folder);
+    let ignored = read_ignore_patterns(root)?;
+    let mut names = Vec::new();
+    for entry in fs::read_dir(root).map_err(|e| tr!(Msg::FolderReadFailed, e))? {
+        let entry = entry.map_err(|e| tr!(Msg::EntryReadFailed, e))?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let is_file = entry.file_type().map_err(|e| tr!(Msg::FileTypeFailed, e))?.is_file();
+        if is_file && !name.starts_with('.') && !ignored.iter().any(|pattern| pattern.matches(&name)) {
+            names.push(name);
+        }
+    }
+    names.sort();
+
+    let mut prefixes: std::collections::BTreeMap<String, usize> = std::collections::BTreeMap::new();
+    for name in &names {
+        if let Some(prefix) = name_prefix(name) {
+            *prefixes.entry(prefix.to_string()).or_default() += 1;
+        }
+    }
+    let prefixes: Vec<String> = prefixes.into_iter()
+        .filter(|(_, count)| *count >= SUGGEST_MIN_GROUP)
+        .map(|(prefix, _)| prefix)
+        .collect();
+
+    let mut rules = Vec::new();
+    for prefix in &prefixes {
+        let destination = root.join(prefix.trim_end_matches(['_', '-', ' ', '.']));
+        rules.push(suggested_rule(tr!(Msg::SuggestedPrefixRule, prefix), &folder, &format!("^{}", regex::escape(prefix)), None, &destination)?);
+    }
+    // 接頭辞のルールで移動されるファイルは拡張子の集計に含めない
+    let mut extensions: std::collections::BTreeMap<String, usize> = std::collections::BTreeMap::new();
+    for name in names.iter().filter(|name| name_prefix(name).is_none_or(|prefix| !prefixes.iter().any(|p| p == prefix))) {
+        let extension = extension_folder(Path::new(name));
+        if extension != "_noext" {
+            *extensions.entry(extension).or_default() += 1;
+        }
+    }
+    for (category, members) in SUGGEST_CATEGORIES {
+        let found: Vec<String> = members.iter()
+            .filter(|extension| extensions.remove(**extension).is_some())
+            .map(|extension| extension.to_string())
+            .collect();
+        if found.is_empty() {
+            continue;
+        }
+        let name = format!("{} ({})", tr!(*category), found.join(", "));
+        rules.push(suggested_rule(name, &folder, "", Some(found), &root.join(tr!(*category)))?);
+    }
+    for (extension, count) in extensions {
+        if count >= 2 {
+            let name = tr!(Msg::SuggestedExtensionRule, extension);
+            rules.push(suggested_rule(name, &folder, "", Some(vec![extension.clone()]), &root.join(&extension))?);
+        }
+    }
+    Ok(rules)
+}
+
+/// ファイル名の先頭の、数字や区切り文字 (`_` `-` 空白 `.`) までの部分 (区切り文字を含む)。3 文字未満なら None
+fn name_prefix(name: &str) -> Option<&str> {
+    let end = name.find(|c: char| c.is_ascii_digit() || matches!(c, '_' | '-' | ' ' | '.'))?;
+    if name[..end].chars().count() < 3 {
+        return None;
+    }
+    // 区切り文字は接頭辞に含めて、`IMG_` と `IMG-` を区別する
+    let end = match name[end..].chars().next() {
+        Some(c) if matches!(c, '_' | '-' | ' ') => end + c.len_utf8(),
+        _ => end,
+    };
+    Some(&name[..end])
+}
+
+/// suggest_rules で返すルール。指定しない項目は設定ファイルに書かなかった場合と同じ既定値にする
+fn suggested_rule(
+    name: String,
+    source: &str,
+    pattern: &str,
+    extensions: Option<Vec<String>>,
+    destination: &Path,
+) -> Result<OrganizeRule, String> {
+    let rule = serde_json::json!({
+        "name": name,
+        "source_folder": source,
+        "pattern": pattern,
+        "destination_folder": destination.to_string_lossy(),
+        "case_insensitive": true,
+        "extensions": extensions,
+    });
+    serde_json::from_value(rule).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn validate_config(config_path: String) -> Result<Vec<ValidationIssue>, String> {
+    let mut config = read_config(&config_path)?;
+    let _language = i18n::use_language(config.language());
+    for rule in &mut config.rules {
+        expand_rule_paths(rule);
+    }
+    let mut issues = Vec::new();
+    let mut seen_names = std::collections::HashSet::new();
+
+    for rule in &config.rules {
+        let mut issue = |severity: Severity, message: String| {
+            issues.push(ValidationIssue {
+                rule_name: rule.name.clone(),
+                severity,
+                message,
+            });
+        };
+        if rule.name.trim().is_empty() {
+            issue(Severity::Error, tr!(Msg::RuleNameEmpty).to_string());
+        } else if !seen_names.insert(rule.name.as_str()) {
+            issue(Severity::Error, tr!(Msg::RuleNameDuplicate, rule.name));
+        }
+        if let Err(errors) = CompiledRule::new(rule) {
+            for e in errors {
+                issue(Severity::Error, e);
+            }
+        }
+        if rule.pattern.is_empty() && !matches!(rule.mode, RuleMode::ByExtension | RuleMode::Alphabetical) {
+            issue(Severity::Warning, tr!(Msg::PatternEmpty).to_string());
+        }
+        if rule.sources().next().is_none() {
+            issue(Severity::Error, tr!(Msg::NoSourceFolder).to_string());
+        }
+        for source in rule.sources() {
+            if Path::new(source).is_dir() {
+                continue;
+            }
+            if !is_source_glob(source) {
+                issue(Severity::Warning, tr!(Msg::SourceFolderMissing, source));
+            } else if let Err(e) = glob::Pattern::new(source) {
+                issue(Severity::Error, tr!(Msg::SourceGlobInvalid, rule.name, source, e));
+            } else {
+                issue(Severity::Warning, tr!(Msg::SourceGlobNoMatch, rule.name, source));
+            }
+        }
+        if let Err(e) = check_destination_writable(&rule.destination_root()) {
+            issue(Severity::Error, e);
+        }
+        if rule.compress.is_some() && matches!(rule.mode, RuleMode::Symlink | RuleMode::Hardlink) {
+            issue(Severity::Error, tr!(Msg::CompressWithLink, rule.name));
+        }
+        if rule.flatten && rule.preserve_structure_from.is_some() {
+            issue(Severity::Warning, tr!(Msg::FlattenIgnored, rule.name));
+        }
+        if !rule.create_destination && !rule.destination_root().is_dir() {
+            issue(Severity::Warning, tr!(Msg::DestinationMissing, rule.name, rule.destination_root().display()));
+        }
+    }
+    if let Some(Err(message)) = config.schedule.as_deref().map(parse_schedule) {
+        issues.push(ValidationIssue { rule_name: String::new(), severity: Severity::Error, message });
+    }
+    Ok(issues)
+}
+
+#[tauri::command]
+async fn test_pattern(pattern: String, sample_names: Vec<String>) -> Result<Vec<PatternTestResult>, String> {
+    let regex = Regex::new(&pattern)
+        .map_err(|e| tr!(Msg::InvalidRegex, pattern, e))?;
+    let results = sample_names.into_iter().map(|name| {
+        let mut result = PatternTestResult {
+            name: String::new(),
+            matched: false,
+            captures: Vec::new(),
+            named_captures: std::collections::BTreeMap::new(),
+        };
+        if let Some(captures) = regex.captures(&name) {
+            result.matched = true;
+            result.captures = captures.iter()
+                .skip(1)
+                .map(|m| m.map(|m| m.as_str().to_string()))
+                .collect();
+            for group_name in regex.capture_names().flatten() {
+                if let Some(m) = captures.name(group_name) {
+                    result.named_captures.insert(group_name.to_string(), m.as_str().to_string());
+                }
+            }
+        }
+        result.name = name;
+        result
+    }).collect();
+    Ok(results)
+}
+
+/// パターンのキャプチャグループを返す。rename_template / destination_folder の `${name}` の補完に使う
+#[tauri::command]
+async fn describe_pattern(pattern: String) -> Result<PatternDescription, String> {
+    let regex = Regex::new(&pattern)
+        .map_err(|e| tr!(Msg::InvalidRegex, pattern, e))?;
+    Ok(PatternDescription {
+        group_count: regex.captures_len() - 1,
+        group_names: regex.capture_names().flatten().map(String::from).collect(),
+    })
+}
+
+#[tauri::command]
+async fn organize_files(
+    app_handle: AppHandle,
+    state: tauri::State<'_, OrganizeState>,
+    config_path: String,
+    dry_run: bool,
+) -> Result<OrganizeReport, String> {
+    organize(app_handle, state.inner(), config_path, dry_run, None).await
+}
+
+/// organize_single_rule で実行するルールの指定。設定ファイルでの番号 (0 始まり) かルール名
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum RuleSelector {
+    Index(usize),
+    Name(String),
+}
+
+impl RuleSelector {
+    fn position(&self, rules: &[OrganizeRule]) -> Result<usize, String> {
+        match self {
+            RuleSelector::Index(index) if *index < rules.len() => Ok(*index),
+            RuleSelector::Index(index) => Err(tr!(Msg::RuleIndexOutOfRange, index)),
+            RuleSelector::Name(name) => rules.iter()
+                .position(|rule| rule.name == *name)
+                .ok_or_else(|| tr!(Msg::RuleNotFound, name)),
+        }
+    }
+}
+
+/// 設定のルールを 1 つだけ、organize_files と同じ処理で実行して結果を返す。
+/// 無効化されているルールも実行する。unmatched_destination、post_run_command と incremental の実行日時の記録は行わない
+#[tauri::command]
+async fn organize_single_rule(
+    app_handle: AppHandle,
+    state: tauri::State<'_, OrganizeState>,
+    config_path: String,
+    rule: RuleSelector,
+    dry_run: bool,
+) -> Result<OrganizeResult, String> {
+    let report = organize(app_handle, state.inner(), config_path, dry_run, Some(rule)).await?;
+    report.results.into_iter().next().ok_or_else(|| tr!(Msg::UnexpectedRuleError).to_string())
+}
+
+/// organize_files / organize_single_rule の本体。`only` を指定した場合はそのルールだけを実行する
+async fn organize(
+    app_handle: AppHandle,
+    state: &OrganizeState,
+    config_path: String,
+    dry_run: bool,
+    only: Option<RuleSelector>,
+) -> Result<OrganizeReport, String> {
+    let config = read_config(&config_path)?;
+    // 同時に別の設定を実行・検証しても混ざらないよう、後処理のコマンドを待つ間も含めてこの設定の言語で実行する
+    i18n::scoped(config.language(), organize_config(app_handle, state, config_path, config, dry_run, only)).await
+}
+
+async fn organize_config(
+    app_handle: AppHandle,
+    state: &OrganizeState,
+    config_path: String,
+    mut config: Config,
+    dry_run: bool,
+    only: Option<RuleSelector>,
+) -> Result<OrganizeReport, String> {
+    let started = Instant::now();
+    let started_at = SystemTime::now();
+    let single = only.is_some();
+    if let Some(selector) = only {
+        let mut rule = config.rules.swap_remove(selector.position(&config.rules)?);
+        rule.enabled = None;
+        config.rules = vec![rule];
+        config.unmatched_destination = None;
+        config.post_run_command = None;
+    }
+    config.sort_rules_by_priority();
+    let compiled = compile_rules(&config.rules)?;
+    state.cancel_requested.store(false, Ordering::SeqCst);
+    *state.confirm_threshold.lock().unwrap() = config.confirm_threshold.filter(|_| !config.auto_confirm);
+    *state.modified_since.lock().unwrap() = if config.incremental { last_run_time(&config_path) } else { None };
+    state.verbose_log.store(config.verbose_log, Ordering::SeqCst);
+    *state.run_limits.lock().unwrap() = config.run_limits();
+    // ログを開けなくても整理は続ける (警告として結果に含める)
+    let (mut log_file, log_open_error) = match open_log_file(&config) {
+        Ok(log_file) => (log_file, None),
+        Err(e) => {
+            eprintln!("{}", tr!(Msg::LogWarning, e));
+            (None, Some(e))
+        }
+    };
+    let logging = log_file.is_some().then_some(config.log_format);
+    if let Some(ref mut log_file) = log_file {
+        match config.log_format {
+            LogFormat::Text => {
+                let mode = if dry_run { tr!(Msg::DryRunTag) } else { "" };
+                writeln!(log_file, "{}", tr!(Msg::RunStarted, Local::now().format("%Y-%m-%d %H:%M:%S"), mode)).ok();
+            }
+            LogFormat::Json => LogRecord { dry_run: Some(dry_run), ..LogRecord::new("started") }.write(log_file),
+        }
+    }
+    for rule in &mut config.rules {
+        for (original, expanded) in expand_rule_paths(rule) {
+            if let (Some(log_file), Some(LogFormat::Text)) = (log_file.as_mut(), logging) {
+                writeln!(log_file, "{}", tr!(Msg::PathExpanded, rule.name, original, expanded)).ok();
+            }
+        }
+    }
+    if let Some(folder) = config.unmatched_destination.as_mut() {
+        *folder = expand_path(folder, &[]);
+    }
+
+    // フォルダを共有しないルールのグループごとに並列実行し、結果はルール順に並べ直す
+    let rules = &config.rules;
+    let first_match_wins = config.first_match_wins;
+    let transactional = config.transactional && !dry_run;
+    let compiled = &compiled;
+    let app_handle = &app_handle;
+    let mut runs: Vec<Option<Result<RuleRun, String>>> = rules.iter().map(|_| None).collect();
+    let mut panicked = false;
+    let language = config.language();
+    // 確認の回答を待つ間も含め、ルールの実行中は非同期ランタイムのワーカーを他のタスクに譲る
+    tokio::task::block_in_place(|| {
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = group_rules_by_folder(rules).into_iter().map(|group| {
+                scope.spawn(move || {
+                    let _language = i18n::use_language(language);
+                    let mut group_runs = Vec::new();
+                    // 別グループとはフォルダを共有しないため、対象ファイルの重複はグループ内だけを見ればよい
+                    let mut claims = first_match_wins.then(ClaimedFiles::new);
+                    for index in group {
+                        let run = match &compiled[index] {
+                            Some(compiled) => {
+                                run_rule(app_handle, state, &rules[index], compiled, dry_run, logging, claims.as_mut())
+                            }
+                            None => Ok(disabled_rule_run(&rules[index], dry_run, logging)),
+                        };
+                        // transactional では元に戻すことになるため、エラーが出たら残りのルールも別グループのルールも実行しない
+                        let failed = match &run {
+                            Ok(run) => !run.result.errors.is_empty(),
+                            Err(_) => true,
+                        };
+                        if transactional && failed {
+                            state.cancel_requested.store(true, Ordering::SeqCst);
+                        }
+                        let stop = match &run {
+                            Ok(run) => run.cancelled || (transactional && failed),
+                            Err(_) => true,
+                        };
+                        group_runs.push((index, run));
+                        if stop {
+                            break;
+                        }
+                    }
+                    group_runs
+                })
+            }).collect();
+            for handle in handles {
+                match handle.join() {
+                    Ok(group_runs) => {
+                        for (index, run) in group_runs {
+                            runs[index] = Some(run);
+                        }
+                    }
+                    Err(_) => panicked = true,
+                }
+            }
+        })
+    });
+    if panicked {
+        return Err(tr!(Msg::UnexpectedRuleError).to_string());
+    }
+    // 無効なルールと、キャンセルなどで実行しなかったルールは含めない
+    let rule_order: Vec<String> = rules.iter().zip(&runs).zip(compiled)
+        .filter(|((_, run), compiled)| run.is_some() && compiled.is_some())
+        .map(|((rule, _), _)| rule.name.clone())
+        .collect();
+
+    let mut results = Vec::new();
+    let mut journal = Vec::new();
+    let mut first_error = None;
+    let mut cancelled = false;
+    let mut consumed = std::collections::HashSet::new();
+    for run in runs.into_iter().flatten() {
+        match run {
+            Ok(run) => {
+                if let (Some(log_file), Some(log)) = (log_file.as_mut(), run.log) {
+                    log_file.write_all(&log).ok();
+                }
+                cancelled |= run.cancelled;
+                consumed.extend(run.matched);
+                journal.extend(run.journal);
+                results.push(run.result);
+            }
+            Err(e) => {
+                if let Some(ref mut log_file) = log_file {
+                    write_log_error(log_file, config.log_format, &e);
+                }
+                if first_error.is_none() {
+                    first_error = Some(e);
+                }
+            }
+        }
+    }
+    if let (Some(folder), None, false) = (&config.unmatched_destination, &first_error, cancelled) {
+        match quarantine_unmatched(state, rules, Path::new(folder), &consumed, dry_run, logging) {
+            Ok(run) => {
+                if let (Some(log_file), Some(log)) = (log_file.as_mut(), run.log) {
+                    log_file.write_all(&log).ok();
+                }
+                journal.extend(run.journal);
+                results.push(run.result);
+            }
+            Err(e) => {
+                if let Some(ref mut log_file) = log_file {
+                    write_log_error(log_file, config.log_format, &e);
+                }
+                first_error = Some(e);
+            }
+        }
+    }
+    if transactional {
+        let failure = first_error.clone()
+            .or_else(|| results.iter().find_map(|result| result.errors.first().cloned()));
+        if let Some(failure) = failure {
+            let undo = restore_journal(&journal);
+            if let Some(ref mut log_file) = log_file {
+                for restored in &undo.restored {
+                    match config.log_format {
+                        LogFormat::Text => {
+                            writeln!(log_file, "{}", tr!(Msg::RolledBack, restored.source, restored.destination)).ok();
+                        }
+                        LogFormat::Json => LogRecord::new("rolled_back")
+                            .paths(Path::new(&restored.source), Some(Path::new(&restored.destination)))
+                            .write(log_file),
+                    }
+                }
+                for removed in &undo.removed {
+                    match config.log_format {
+                        LogFormat::Text => {
+                            writeln!(log_file, "{}", tr!(Msg::RolledBackRemoved, removed)).ok();
+                        }
+                        LogFormat::Json => LogRecord::new("rolled_back_removed").paths(Path::new(removed), None).write(log_file),
+                    }
+                }
+                for skipped in &undo.skipped {
+                    write_log_error(log_file, config.log_format, skipped);
+                }
+            }
+            let mut message = tr!(Msg::TransactionRolledBack, undo.restored.len(), undo.removed.len(), failure);
+            if !undo.skipped.is_empty() {
+                message.push('\n');
+                message.push_str(&tr!(Msg::RollbackIncomplete, undo.skipped.join("\n")));
+            }
+            first_error = Some(message);
+            journal.clear();
+        }
+    }
+    // 何も動かさなかった実行でも前回の記録を残すと、取り消しで前回の整理が戻ってしまう
+    if !dry_run {
+        if let Err(e) = write_journal(&journal) {
+            if let Some(ref mut log_file) = log_file {
+                write_log_warning(log_file, config.log_format, &e);
+            }
+            if let Some(last) = results.last_mut() {
+                last.warnings.push(e);
+            }
+        }
+    }
+    // 処理できなかったファイルを次回も対象にするため、すべて完了した場合だけ記録する
+    let completed = first_error.is_none() && !cancelled && results.iter().all(|result| result.errors.is_empty());
+    if config.incremental && !dry_run && completed && !single {
+        if let Err(e) = save_last_run_time(&config_path, started_at) {
+            if let Some(ref mut log_file) = log_file {
+                write_log_warning(log_file, config.log_format, &e);
+            }
+            if let Some(last) = results.last_mut() {
+                last.warnings.push(e);
+            }
+        }
+    }
+    if let (Some(e), Some(last)) = (log_open_error, results.last_mut()) {
+        last.warnings.push(e);
+    }
+    *state.last_results.lock().unwrap() = results.clone();
+    let entry = RunHistoryEntry::new(&config_path, dry_run, &results, first_error.clone());
+    if let Err(e) = append_history(entry, config.history_keep.unwrap_or(DEFAULT_HISTORY_KEEP)) {
+        if let Some(ref mut log_file) = log_file {
+            write_log_warning(log_file, config.log_format, &e);
+        }
+        if let Some(last) = results.last_mut() {
+            last.warnings.push(e);
+        }
+    }
+    match first_error {
+        Some(e) => Err(e),
+        None => {
+            let summary = OrganizeSummary::from_results(&results, started.elapsed());
+            let mut post_run = None;
+            if let (Some(command), false) = (&config.post_run_command, dry_run) {
+                let hook = run_post_run_command(app_handle, command, &config_path, &results, &summary).await;
+                if let Some(ref mut log_file) = log_file {
+                    write_post_run_log(log_file, config.log_format, &hook);
+                }
+                post_run = Some(hook);
+            }
+            let estimated_seconds = dry_run.then(|| estimate_transfer_seconds(&config.rules, &results));
+            Ok(OrganizeReport { results, summary, rule_order, post_run, estimated_seconds })
+        }
+    }
+}
+
+/// estimate_transfer_seconds で書き込み速度を測るために書き込むデータの大きさ
+const BENCHMARK_BYTES: usize = 8 * 1024 * 1024;
+
+/// 書き込み速度を測れなかった場合に仮定する速度 (バイト/秒)
+const FALLBACK_WRITE_SPEED: f64 = 50.0 * 1024.0 * 1024.0;
+
+/// rename やリンクで済むファイルにも見込む 1 件あたりの時間 (秒)
+const PER_FILE_SECONDS: f64 = 0.002;
+
+/// ドライランの結果から、実際に実行した場合のおおよその所要時間 (秒) を見積もる。
+/// コピーが必要なファイルは宛先のボリュームごとに書き込み速度を測ってサイズを割り、rename やリンクで済むものは件数分だけ見込む
+fn estimate_transfer_seconds(rules: &[OrganizeRule], results: &[OrganizeResult]) -> f64 {
+    let mut speeds: Vec<(PathBuf, f64)> = Vec::new();
+    let mut seconds = 0.0;
+    for result in results {
+        // 未分類のファイルの移動 (unmatched_destination) は move と同じに扱う
+        let rule = rules.iter().find(|rule| rule.name == result.rule_name);
+        for moved in &result.moved {
+            seconds += PER_FILE_SECONDS;
+            let (source, destination) = (Path::new(&moved.source), Path::new(&moved.destination));
+            let copied = match rule.map(|rule| (rule.mode, rule.compress.is_some())) {
+                Some((_, true) | (RuleMode::Copy, _)) => true,
+                Some((RuleMode::Symlink | RuleMode::Hardlink, _)) => false,
+                _ => !is_same_volume(source, destination),
+            };
+            let Some(dir) = destination.ancestors().skip(1).find(|path| path.is_dir()).filter(|_| copied) else {
+                continue;
+            };
+            let speed = match speeds.iter().find(|(measured, _)| is_same_volume(measured, dir)) {
+                Some(&(_, speed)) => speed,
+                None => {
+                    let speed = measure_write_speed(dir).unwrap_or(FALLBACK_WRITE_SPEED);
+                    speeds.push((dir.to_path_buf(), speed));
+                    speed
+                }
+            };
+            seconds += moved.size as f64 / speed;
+        }
+    }
+    seconds
+}
+
+/// `dir` に BENCHMARK_BYTES の一時ファイルを書いてディスクへ書き出すまでの時間から、書き込み速度 (バイト/秒) を測る。
+/// 一時ファイルはすぐに削除する
+fn measure_write_speed(dir: &Path) -> Option<f64> {
+    let path = dir.join(format!(".file-organizer-benchmark-{}", std::process::id()));
+    let started = Instant::now();
+    let written = fs::File::create(&path).and_then(|mut file| {
+        file.write_all(&vec![0x5a; BENCHMARK_BYTES])?;
+        file.sync_all()
+    });
+    let elapsed = started.elapsed().as_secs_f64();
+    fs::remove_file(&path).ok();
+    written.ok()?;
+    (elapsed > 0.0).then(|| BENCHMARK_BYTES as f64 / elapsed)
+}
+
+/// post_run_command を OS のシェルで実行し、出力を返す
+async fn run_post_run_command(
+    app_handle: &AppHandle,
+    command: &str,
+    config_path: &str,
+    results: &[OrganizeResult],
+    summary: &OrganizeSummary,
+) -> PostRunResult {
+    let shell = app_handle.shell();
+    #[cfg(target_os = "windows")]
+    let hook = shell.command("cmd").args(["/C", command]);
+    #[cfg(not(target_os = "windows"))]
+    let hook = shell.command("sh").args(["-c", command]);
+    let warnings: usize = results.iter().map(|result| result.warnings.len()).sum();
+    let hook = hook.envs([
+        ("FILE_ORGANIZER_CONFIG", config_path.to_string()),
+        ("FILE_ORGANIZER_TOTAL_MOVED", summary.total_moved.to_string()),
+        ("FILE_ORGANIZER_TOTAL_BYTES", summary.total_bytes.to_string()),
+        ("FILE_ORGANIZER_RULES", summary.rules.len().to_string()),
+        ("FILE_ORGANIZER_WARNINGS", warnings.to_string()),
+        ("FILE_ORGANIZER_ELAPSED_MS", summary.elapsed_ms.to_string()),
+    ]);
+    let mut result = PostRunResult {
+        command: command.to_string(),
+        success: false,
+        exit_code: None,
+        stdout: String::new(),
+        stderr: String::new(),
+        error: None,
+    };
+    match hook.output().await {
+        Ok(output) => {
+            result.success = output.status.success();
+            result.exit_code = output.status.code();
+            result.stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+            result.stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+        }
+        Err(e) => result.error = Some(tr!(Msg::PostRunFailed, e)),
+    }
+    result
+}
+
+fn write_post_run_log(log_file: &mut impl Write, format: LogFormat, hook: &PostRunResult) {
+    match format {
+        LogFormat::Text => {
+            let status = hook.exit_code.map_or_else(|| "-".to_string(), |code| code.to_string());
+            writeln!(log_file, "{}", tr!(Msg::PostRunFinished, hook.command, status)).ok();
+            for (label, output) in [("stdout", &hook.stdout), ("stderr", &hook.stderr)] {
+                if !output.trim().is_empty() {
+                    writeln!(log_file, "[{}]\n{}", label, output.trim_end()).ok();
+                }
+            }
+            if let Some(error) = &hook.error {
+                writeln!(log_file, "{}", tr!(Msg::LogError, error)).ok();
+            }
+        }
+        LogFormat::Json => {
+            let message = hook.error.clone().unwrap_or_else(|| format!("{}{}", hook.stdout, hook.stderr));
+            LogRecord::new(if hook.success { "post_run" } else { "post_run_failed" })
+                .message(message)
+                .write(log_file);
+        }
+    }
+}
+
+/// ルールのソース・宛先フォルダの `~` と環境変数を展開する。変化したパスの (元, 展開後) を返す。
+/// 宛先の `$name` / `${name}` が pattern の名前付きグループを指す場合は、環境変数として展開しない
+fn expand_rule_paths(rule: &mut OrganizeRule) -> Vec<(String, String)> {
+    let group_names: Vec<String> = Regex::new(&rule.pattern)
+        .map(|regex| regex.capture_names().flatten().map(String::from).collect())
+        .unwrap_or_default();
+    let mut changes = Vec::new();
+    let folders = std::iter::once((&mut rule.source_folder, &[][..]))
+        .chain(rule.source_folders.iter_mut().flatten().map(|folder| (folder, &[][..])))
+        .chain(rule.preserve_structure_from.iter_mut().map(|folder| (folder, &[][..])))
+        .chain([(&mut rule.destination_folder, &group_names[..])]);
+    for (folder, keep) in folders {
+        let expanded = expand_path(folder, keep);
+        if expanded != *folder {
+            changes.push((std::mem::replace(folder, expanded.clone()), expanded));
+        }
+    }
+    for (pattern, folders) in expand_source_globs(rule) {
+        changes.push((pattern, folders.join(", ")));
+    }
+    changes
+}
+
+/// ソースフォルダの `*` `?` `[...]` `{a,b}` を展開し、マッチするフォルダに置き換える。
+/// どのフォルダにもマッチしなかったものは実行時に警告するため元のまま残す。展開した glob とマッチしたフォルダを返す
+fn expand_source_globs(rule: &mut OrganizeRule) -> Vec<(String, Vec<String>)> {
+    let mut expanded = Vec::new();
+    let mut folders = Vec::new();
+    for source in rule.sources() {
+        if !is_source_glob(source) {
+            folders.push(source.clone());
+            continue;
+        }
+        let matched: Vec<String> = expand_braces(source).iter()
+            .filter_map(|pattern| glob::glob(pattern).ok())
+            .flatten()
+            .filter_map(Result::ok)
+            .filter(|path| path.is_dir())
+            .map(|path| path.to_string_lossy().into_owned())
+            .collect();
+        if matched.is_empty() {
+            folders.push(source.clone());
+        } else {
+            folders.extend(matched.iter().cloned());
+            expanded.push((source.clone(), matched));
+        }
+    }
+    if !expanded.is_empty() {
+        // 複数の glob に重ねてマッチしたフォルダを 2 回走査しないようにする
+        let mut seen = std::collections::HashSet::new();
+        folders.retain(|folder| seen.insert(folder.clone()));
+        rule.source_folder = String::new();
+        rule.source_folders = Some(folders);
+    }
+    expanded
+}
+
+/// glob の記号を含むソースフォルダか。`[2021]` のような名前の実在するフォルダはそのまま扱う
+fn is_source_glob(source: &str) -> bool {
+    source.contains(['*', '?', '[', '{']) && !Path::new(source).exists()
+}
+
+/// `Project_{2021,2022}` を `Project_2021` と `Project_2022` に展開する。入れ子の `{}` にも対応する
+fn expand_braces(pattern: &str) -> Vec<String> {
+    let Some(open) = pattern.find('{') else {
+        return vec![pattern.to_string()];
+    };
+    let mut depth = 0;
+    let mut alternatives = Vec::new();
+    let mut start = open + 1;
+    for (i, c) in pattern[open..].char_indices().map(|(i, c)| (open + i, c)) {
+        match c {
+            '{' => depth += 1,
+            ',' if depth == 1 => {
+                alternatives.push(&pattern[start..i]);
+                start = i + 1;
+            }
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    alternatives.push(&pattern[start..i]);
+                    let (prefix, suffix) = (&pattern[..open], &pattern[i + 1..]);
+                    return alternatives.into_iter()
+                        .flat_map(|alternative| expand_braces(&format!("{}{}{}", prefix, alternative, suffix)))
+                        .collect();
+                }
+            }
+            _ => {}
+        }
+    }
+    // 閉じていない `{` は文字として扱う
+    vec![pattern.to_string()]
+}
+
+/// 先頭の `~` をホームディレクトリに、`%VAR%` / `$VAR` / `${VAR}` を環境変数の値に置き換える。
+/// 未定義の環境変数と、`$VAR` / `${VAR}` の VAR が `keep` に含まれるものはそのまま残す。
+fn expand_path(path: &str, keep: &[String]) -> String {
+    static ENV_VAR: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(r"%([A-Za-z_][A-Za-z0-9_]*)%|\$\{([A-Za-z_][A-Za-z0-9_]*)\}|\$([A-Za-z_][A-Za-z0-9_]*)").unwrap()
+    });
+    let expanded = ENV_VAR.replace_all(path, |caps: &Captures| {
+        if let Some(name) = caps.get(2).or_else(|| caps.get(3)) {
+            if keep.iter().any(|kept| kept == name.as_str()) {
+                return caps[0].to_string();
+            }
+        }
+        let name = caps.get(1).or_else(|| caps.get(2)).or_else(|| caps.get(3))
+            .map_or("", |m| m.as_str());
+        std::env::var(name).unwrap_or_else(|_| caps[0].to_string())
+    });
+    let rest = if expanded == "~" {
+        Some("")
+    } else {
+        expanded.strip_prefix("~/").or_else(|| expanded.strip_prefix("~\\"))
+    };
+    match (rest, dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest).to_string_lossy().into_owned(),
+        _ => expanded.into_owned(),
+    }
+}
+
+/// ログファイルのパス。Config で指定がなければアプリの設定ディレクトリに置く
+fn log_file_path(config: &Config) -> Result<PathBuf, String> {
+    match &config.log_file {
+        Some(path) => Ok(PathBuf::from(path)),
+        None => Ok(app_config_dir()?.join("file-organizer.log")),
+    }
+}
+
+const DEFAULT_MAX_LOG_BYTES: u64 = 5 * 1024 * 1024;
+const DEFAULT_LOG_KEEP: usize = 3;
+
+/// ログファイルを追記モードで開く。サイズが上限を超えていれば先にローテーションする。ログが無効な場合は None
+fn open_log_file(config: &Config) -> Result<Option<fs::File>, String> {
+    if config.logging == Some(false) {
+        return Ok(None);
+    }
+    let path = log_file_path(config)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| tr!(Msg::LogDirCreateFailed, e))?;
+    }
+    let max_bytes = config.max_log_bytes.unwrap_or(DEFAULT_MAX_LOG_BYTES);
+    if fs::metadata(&path).is_ok_and(|m| m.len() > max_bytes) {
+        // ローテーションに失敗してもログの追記は続ける
+        rotate_log_file(&path, config.log_keep.unwrap_or(DEFAULT_LOG_KEEP)).ok();
+    }
+    let file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| tr!(Msg::LogOpenFailed, e))?;
+    Ok(Some(file))
+}
+
+/// `file-organizer.log` を `file-organizer.log.1` に、既存の `.1` を `.2` に…とずらし、`keep` より古いものは削除する
+fn rotate_log_file(path: &Path, keep: usize) -> io::Result<()> {
+    let rotated = |index: usize| {
+        let mut name = path.as_os_str().to_owned();
+        name.push(format!(".{}", index));
+        PathBuf::from(name)
+    };
+    if keep == 0 {
+        return fs::remove_file(path);
+    }
+    let oldest = rotated(keep);
+    if oldest.exists() {
+        fs::remove_file(&oldest)?;
+    }
+    for index in (1..keep).rev() {
+        let from = rotated(index);
+        if from.exists() {
+            fs::rename(&from, rotated(index + 1))?;
+        }
+    }
+    fs::rename(path, rotated(1))
+}
+
+/// ルールの外で起きた警告をログに書く
+fn write_log_warning(log_file: &mut impl Write, format: LogFormat, warning: &str) {
+    match format {
+        LogFormat::Text => {
+            writeln!(log_file, "{}", tr!(Msg::LogWarning, warning)).ok();
+        }
+        LogFormat::Json => LogRecord::new("warning").message(warning).write(log_file),
+    }
+}
+
+/// ルールの外で起きたエラーをログに書く
+fn write_log_error(log_file: &mut impl Write, format: LogFormat, error: &str) {
+    match format {
+        LogFormat::Text => {
+            writeln!(log_file, "{}", tr!(Msg::LogError, error)).ok();
+        }
+        LogFormat::Json => LogRecord::new("error").message(error).write(log_file),
+    }
+}
+
+/// first_match_wins で、既にルールが対象にしたパスとそのルール名
+type ClaimedFiles = std::collections::HashMap<PathBuf, String>;
+
+/// 1 つのルールの実行結果
+struct RuleRun {
+    result: OrganizeResult,
+    /// 取り消し用に記録する移動
+    journal: Vec<JournalEntry>,
+    /// ルール単位でバッファしたログ (並列実行してもルール順に書き出すため)。形式は Config の log_format に従う
+    log: Option<Vec<u8>>,
+    cancelled: bool,
+    /// パターンとフィルタにマッチしたソースのパス (unmatched_destination で対象外にする)
+    matched: Vec<PathBuf>,
+}
+
+/// 実行中のルールの結果とログ。ログには Config の log_format に従い、どちらか一方だけを書く
+struct RuleRecorder {
+    result: OrganizeResult,
+    journal: Vec<JournalEntry>,
+    matched: Vec<PathBuf>,
+    /// テキスト形式のログ (途中経過も書く)
+    log_file: Option<Vec<u8>>,
+    /// JSON Lines 形式のログ (ファイル単位の結果だけを書く)
+    records: Option<Vec<u8>>,
+}
+
+impl RuleRecorder {
+    /// 空の実行結果を作り、見出しをテキストのログに書く
+    fn new(rule_name: String, header: impl std::fmt::Display, dry_run: bool, logging: Option<LogFormat>) -> Self {
+        let mut recorder = RuleRecorder {
+            result: OrganizeResult {
+                rule_name,
+                dry_run,
+                moved: Vec::new(),
+                bytes: 0,
+                archives: Vec::new(),
+                skipped: Vec::new(),
+                warnings: Vec::new(),
+                errors: Vec::new(),
+            },
+            journal: Vec::new(),
+            matched: Vec::new(),
+            log_file: (logging == Some(LogFormat::Text)).then(Vec::new),
+            records: (logging == Some(LogFormat::Json)).then(Vec::new),
+        };
+        recorder.log(header);
+        recorder
+    }
+
+    fn start(rule: &OrganizeRule, dry_run: bool, logging: Option<LogFormat>) -> Self {
+        RuleRecorder::new(rule.name.clone(), tr!(Msg::RuleHeader, rule.name, rule.pattern), dry_run, logging)
+    }
+
+    /// テキストのログにだけ書く
+    fn log(&mut self, line: impl std::fmt::Display) {
+        if let Some(ref mut log_file) = self.log_file {
+            writeln!(log_file, "{}", line).ok();
+        }
+    }
+
+    /// JSON Lines のログにだけ書く
+    fn record(&mut self, record: LogRecord) {
+        if let Some(ref mut records) = self.records {
+            record.rule(&self.result.rule_name).write(records);
+        }
+    }
+
+    /// 両方のログに書き、"error" はエラー、それ以外は警告として結果に入れる
+    fn note(&mut self, record: LogRecord, message: String) {
+        let action = record.action;
+        if matches!(action, "warning" | "limit_reached") {
+            self.log(tr!(Msg::LogWarning, message));
+        } else {
+            self.log(&message);
+        }
+        self.record(record.message(&message));
+        if action == "error" {
+            self.result.errors.push(message);
+        } else {
+            self.result.warnings.push(message);
+        }
+    }
+
+    fn finish(self, cancelled: bool) -> RuleRun {
+        RuleRun {
+            result: self.result,
+            journal: self.journal,
+            log: self.log_file.or(self.records),
+            cancelled,
+            matched: self.matched,
+        }
+    }
+}
+
+/// 無効化されたルールの実行結果 (スキップした旨の警告のみ)
+fn disabled_rule_run(rule: &OrganizeRule, dry_run: bool, logging: Option<LogFormat>) -> RuleRun {
+    let mut recorder = RuleRecorder::start(rule, dry_run, logging);
+    recorder.note(LogRecord::new("disabled"), tr!(Msg::RuleDisabled, rule.name));
+    recorder.finish(false)
+}
+
+fn run_rule(
+    app_handle: &AppHandle,
+    state: &OrganizeState,
+    rule: &OrganizeRule,
+    compiled: &CompiledRule,
+    dry_run: bool,
+    logging: Option<LogFormat>,
+    mut claims: Option<&mut ClaimedFiles>,
+) -> Result<RuleRun, String> {
+    let mut recorder = RuleRecorder::start(rule, dry_run, logging);
+    // 宛先にキャプチャのプレースホルダがあれば、ファイルごとに展開する。走査前の確認はその手前のフォルダで行う
+    let dest_root = rule.destination_root();
+    let dest_path = dest_root.as_path();
+    let templated = dest_path != Path::new(&rule.destination_folder);
+    // オフラインの共有に対してファイルごとにエラーを出したり、応答を待って止まったりしないよう先に確認する
+    if let Some(share) = network_share_root(&rule.destination_folder) {
+        let timeout = Duration::from_millis(rule.network_timeout_ms.unwrap_or(DEFAULT_NETWORK_TIMEOUT_MS));
+        if let Err(reason) = check_reachable(&share, timeout) {
+            recorder.note(LogRecord::new("warning"), tr!(Msg::NetworkUnreachable, rule.name, share.display(), reason));
+            return Ok(recorder.finish(false));
+        }
+    }
+    let mut source_paths = Vec::new();
+    for source in rule.sources() {
+        if Path::new(source).exists() {
+            source_paths.push(Path::new(source));
+            continue;
+        }
+        let warning = if is_source_glob(source) {
+            tr!(Msg::SourceGlobNoMatch, rule.name, source)
+        } else {
+            tr!(Msg::SourceFolderMissing, source)
+        };
+        recorder.note(LogRecord::new("warning"), warning);
+    }
+    if source_paths.is_empty() {
+        return Ok(recorder.finish(false));
+    }
+    // 宛先がソースと同じだと自分自身への移動になり、再帰走査ではソース配下の宛先が再びマッチしてしまう
+    for source_path in &source_paths {
+        if !is_same_or_inside(source_path, dest_path) {
+            continue;
+        }
+        let warning = if !templated && is_same_or_inside(dest_path, source_path) {
+            tr!(Msg::SourceIsDestination, rule.name, source_path.display())
+        } else if rule.recursive {
+            tr!(Msg::DestinationInsideSource, rule.name, rule.destination_folder, source_path.display())
+        } else {
+            continue;
+        };
+        recorder.note(LogRecord::new("warning"), warning);
+        return Ok(recorder.finish(false));
+    }
+    // ハードリンクはボリュームをまたげないため、ファイルごとに失敗する前にルール単位で確認する
+    if rule.mode == RuleMode::Hardlink {
+        if let Some(source_path) = source_paths.iter().find(|source_path| !is_same_volume(source_path, dest_path)) {
+            recorder.note(LogRecord::new("error"), tr!(Msg::HardlinkCrossVolume, source_path.display(), dest_path.display()));
+            return Ok(recorder.finish(false));
+        }
+    }
+    // リンクは元ファイルを指すだけなので、圧縮した内容を置けない
+    if rule.compress.is_some() && matches!(rule.mode, RuleMode::Symlink | RuleMode::Hardlink) {
+        recorder.note(LogRecord::new("error"), tr!(Msg::CompressWithLink, rule.name));
+        return Ok(recorder.finish(false));
+    }
+    // 宛先フォルダを作成 (ドライランでは作成しない)。create_destination が false ならパスの誤りとみなしてスキップする
+    if !dest_path.exists() {
+        if !rule.create_destination {
+            recorder.note(LogRecord::new("warning"), tr!(Msg::DestinationMissing, rule.name, dest_path.display()));
+            return Ok(recorder.finish(false));
+        }
+        if !dry_run {
+            fs::create_dir_all(dest_path)
+                .map_err(|e| tr!(Msg::DestFolderCreateFailed, e))?;
+        }
+    }
+    let regex = &compiled.regex;
+    let mut files = Vec::new();
+    let mut hidden = Vec::new();
+    for source_path in &source_paths {
+        let (collected, skipped) = collect_source_files_with_hidden(source_path, rule)?;
+        files.extend(collected);
+        hidden.extend(skipped);
+    }
+    recorder.result.skipped.extend(hidden.iter().map(|path| SkippedFile::new(path, SkipReason::Hidden, None)));
+    // 隠しファイルはどのルールでも大量にあるので、詳細ログのときだけ記録する
+    if state.verbose_log.load(Ordering::SeqCst) {
+        for path in &hidden {
+            let message = tr!(Msg::HiddenSkipped, path.display());
+            recorder.log(&message);
+            recorder.record(LogRecord::new("skipped").paths(path, None).message(message));
+        }
+    }
+    // 広すぎるパターンでソースを空にしないよう、ソースから取り出せる数を決めておく (コピーとリンクは元が残る)
+    let removable = rule.keep_at_least
+        .filter(|_| !rule.mode.keeps_source())
+        .map(|keep| files.len().saturating_sub(keep));
+    let mut taken = 0;
+    // パターンとフィルタを満たすファイルを先に絞り込み、進捗の総数とする
+    let modified_since = *state.modified_since.lock().unwrap();
+    let mut matched_files = select_matching_files(
+        rule, compiled, files, modified_since, &mut recorder.log_file, &mut recorder.result.skipped,
+    );
+    recorder.matched = matched_files.iter().map(|file| file.path.clone()).collect();
+    if let Some(claims) = claims.as_deref_mut() {
+        matched_files.retain(|file| {
+            let Some(owner) = file.path.ancestors().find_map(|path| claims.get(path)) else {
+                return true;
+            };
+            recorder.note(
+                LogRecord::new("skipped").paths(&file.path, None),
+                tr!(Msg::ClaimedByEarlierRule, file.path.display(), owner),
+            );
+            false
+        });
+        for file in &matched_files {
+            claims.insert(file.path.clone(), rule.name.clone());
+        }
+    }
+    let total = matched_files.len();
+    let confirm_threshold = *state.confirm_threshold.lock().unwrap();
+    let unattended = state.unattended.load(Ordering::SeqCst);
+    if !dry_run && confirm_threshold.is_some_and(|threshold| total > threshold)
+        && (unattended || !wait_for_confirmation(app_handle, state, &rule.name, total))
+    {
+        // 実行しなかったファイルは後のルールが扱えるようにする
+        if let Some(claims) = claims.as_deref_mut() {
+            for file in &matched_files {
+                claims.remove(&file.path);
+            }
+        }
+        let warning = if unattended {
+            tr!(Msg::ConfirmUnattended, rule.name, total)
+        } else {
+            tr!(Msg::ConfirmDeclined, rule.name, total)
+        };
+        recorder.note(LogRecord::new("declined"), warning);
+        return Ok(recorder.finish(false));
+    }
+    // 途中で容量が足りなくなって中途半端に移動しないよう、コピーが必要な分の空きを先に確認する
+    if !dry_run {
+        let required: u64 = matched_files.iter()
+            .filter(|file| match rule.mode {
+                RuleMode::Copy => true,
+                RuleMode::Symlink | RuleMode::Hardlink => false,
+                RuleMode::Move | RuleMode::ByExtension | RuleMode::Alphabetical => !is_same_volume(&file.path, dest_path),
+            })
+            .map(|file| path_size(&file.path))
+            .sum();
+        let available = dest_path.ancestors()
+            .find(|path| path.exists())
+            .and_then(|path| fs2::available_space(path).ok());
+        if let Some(available) = available.filter(|&available| required > available) {
+            recorder.note(LogRecord::new("warning"), tr!(Msg::InsufficientSpace, rule.name, required, available));
+            return Ok(recorder.finish(false));
+        }
+    }
+    // flatten で宛先に置く予定のパス。ドライランでも別サブフォルダの同名ファイルの衝突を検出できるようにする
+    let mut flattened = std::collections::HashSet::new();
+    // zip に入れるファイル (元ファイル, アーカイブ内のパスとしての移動先, サイズ, 上限に数えたサイズ)。ループの後でまとめて書き込む
+    let mut zip_entries: Vec<(PathBuf, PathBuf, u64, u64)> = Vec::new();
+    let mut zipped = std::collections::HashSet::new();
+    // 同時に実行する転送の数 (未指定なら 1 件ずつ)。並列で転送中のものは pending に転送した順に並ぶ
+    let concurrency = rule.max_concurrency.unwrap_or(1).max(1);
+    let permits = Semaphore::new(concurrency);
+    let mut pending: std::collections::VecDeque<PendingTransfer> = std::collections::VecDeque::new();
+    // 転送中のファイルの結果を記録するため、キャンセルや上限ではすぐに返さず、残りを待ってから返す
+    let mut stopped = false;
+    let mut cancelled = false;
+    let mut files = matched_files.into_iter().enumerate();
+    loop {
+        let next = if stopped { None } else { files.next() };
+        // 転送の結果を転送した順に記録する。次のファイルがある間は終わったものだけを、最後はすべてを待って記録する
+        while pending.front().is_some_and(|pending| next.is_none() || pending.job.is_finished()) {
+            let Some(PendingTransfer { source_file, dest_file, original_size, reserved, job }) = pending.pop_front() else {
+                break;
+            };
+            let (moved, attempts) = job.wait();
+            for attempt in 1..=attempts {
+                recorder.log(tr!(Msg::RetryInUse, source_file.display(), attempt));
+            }
+            match moved {
+                Ok(outcome) => {
+                    for warning in outcome.warnings {
+                        recorder.note(LogRecord::new("warning").paths(&source_file, Some(&dest_file)), warning);
+                    }
+                    let (line, action) = match rule.mode {
+                        RuleMode::Move | RuleMode::ByExtension | RuleMode::Alphabetical => (
+                            tr!(Msg::Moved, outcome.strategy, source_file.display(), dest_file.display()), "moved",
+                        ),
+                        RuleMode::Copy => (tr!(Msg::Copied, source_file.display(), dest_file.display()), "copied"),
+                        RuleMode::Symlink | RuleMode::Hardlink => (
+                            tr!(Msg::Linked, outcome.strategy, source_file.display(), dest_file.display()), "linked",
+                        ),
+                    };
+                    recorder.log(line);
+                    recorder.record(LogRecord { strategy: Some(outcome.strategy), ..LogRecord::new(action) }
+                        .paths(&source_file, Some(&dest_file)));
+                    // 後のルールが移動先を走査しても、移動したファイルを再び扱わないようにする
+                    if let Some(claims) = claims.as_deref_mut() {
+                        claims.insert(dest_file.clone(), rule.name.clone());
+                    }
+                    let metadata = fs::metadata(&dest_file).ok();
+                    let mut size = metadata.as_ref().map_or(0, |m| m.len());
+                    if let Some(original_size) = original_size {
+                        let archive = CompressedArchive::new(&dest_file, 1, original_size, size);
+                        recorder.log(tr!(Msg::ArchiveWritten, dest_file.display(), 1, compression_percent(&archive)));
+                        recorder.result.archives.push(archive);
+                        size = original_size;
+                    }
+                    recorder.result.bytes += size;
+                    // コピーとリンクは元ファイルが残っているため、取り消しでは作成したものを削除する。
+                    // 削除の前にはリンク自体が変更されていないかを確かめる
+                    let (action, written) = match (rule.mode.keeps_source(), rule.compress) {
+                        (true, _) => (JournalAction::Remove, fs::symlink_metadata(&dest_file).ok()),
+                        (false, Some(CompressMode::Gzip)) => (JournalAction::Gunzip, metadata),
+                        (false, _) => (JournalAction::Move, metadata),
+                    };
+                    recorder.journal.push(JournalEntry {
+                        source: source_file.to_string_lossy().into_owned(),
+                        destination: dest_file.to_string_lossy().into_owned(),
+                        size: written.as_ref().map_or(0, |m| m.len()),
+                        modified: written.and_then(|m| m.modified().ok()),
+                        action,
+                    });
+                    let entry = MovedFile::new(&source_file, &dest_file, outcome.strategy, size);
+                    // メタデータは補助的な記録なので、書けなくても移動は成功として扱う
+                    if rule.write_sidecar {
+                        if let Err(warning) = write_sidecar(&rule.name, &entry) {
+                            recorder.note(LogRecord::new("warning").paths(&source_file, Some(&dest_file)), warning);
+                        }
+                    }
+                    recorder.result.moved.push(entry);
+                }
+                // 移動しなかったファイルは上限に数えない
+                Err(e) if e.in_use => {
+                    release_run_limit(state, reserved);
+                    let warning = tr!(Msg::SkippedInUse, source_file.display(), e.message);
+                    recorder.result.skipped.push(SkippedFile::new(&source_file, SkipReason::Locked, Some(warning.clone())));
+                    recorder.note(LogRecord::new("skipped").paths(&source_file, Some(&dest_file)), warning);
+                }
+                Err(e) => {
+                    release_run_limit(state, reserved);
+                    recorder.note(LogRecord::new("error").paths(&source_file, Some(&dest_file)), e.message);
+                }
+            }
+        }
+        let Some((processed, file)) = next else {
+            break;
+        };
+        // ファイル単位で確認するため、移動途中のファイルが残ることはない
+        if state.cancel_requested.load(Ordering::SeqCst) {
+            recorder.note(LogRecord::new("cancelled"), tr!(Msg::Cancelled).to_string());
+            (stopped, cancelled) = (true, true);
+            continue;
+        }
+        if removable.is_some_and(|removable| taken >= removable) {
+            let keep = rule.keep_at_least.unwrap_or(0);
+            recorder.note(LogRecord::new("warning"), tr!(Msg::KeepAtLeastReached, rule.name, keep, total - processed));
+            stopped = true;
+            continue;
+        }
+        emit_progress(app_handle, &rule.name, processed, total, &file.path);
+        let file_name_str = file.file_name.to_string_lossy();
+        let subject = match_subject(rule, &file);
+        let Some(captures) = regex.captures(&subject) else {
+            continue;
+        };
+        let source_file = file.path;
+        let mut dest_name = match &rule.rename_template {
+            Some(template) => match expand_rename_template(&captures, template) {
+                Ok(name) => {
+                    recorder.log(tr!(Msg::Renamed, file_name_str, name.display()));
+                    name
+                }
+                Err(e) => {
+                    recorder.note(LogRecord::new("error").paths(&source_file, None), e);
+                    continue;
+                }
+            },
+            None => PathBuf::from(&file.file_name),
+        };
+        let mut dest_dir = if templated {
+            match expand_destination_template(&captures, regex, &rule.destination_folder) {
+                Ok(dir) => {
+                    recorder.log(tr!(Msg::DestinationResolved, dir.display()));
+                    dir
+                }
+                Err(reason) => {
+                    recorder.note(
+                        LogRecord::new("skipped").paths(&source_file, None),
+                        tr!(Msg::FileSkipped, file.relative_path.display(), reason),
+                    );
+                    continue;
+                }
+            }
+        } else {
+            dest_path.to_path_buf()
+        };
+        match rule.mode {
+            RuleMode::ByExtension => dest_dir.push(extension_folder(&dest_name)),
+            RuleMode::Alphabetical => dest_dir.push(initial_folder(&dest_name)),
+            _ => {}
+        }
+        if let Some(format) = &rule.date_subfolder {
+            match date_subfolder_for(&source_file, format) {
+                Ok((date_dir, time_kind)) => {
+                    recorder.log(tr!(Msg::DateFolder, date_dir.display(), time_kind));
+                    dest_dir.push(date_dir);
+                }
+                Err(e) => {
+                    recorder.note(LogRecord::new("error").paths(&source_file, None), e);
+                    continue;
+                }
+            }
+        }
+        if let (Some(options), Some(name)) = (&rule.sanitize, dest_name.file_name()) {
+            let name = name.to_string_lossy();
+            let sanitized = sanitize_file_name(&name, options);
+            if sanitized != name {
+                recorder.log(tr!(Msg::Sanitized, name, sanitized));
+                dest_name.set_file_name(sanitized);
+            }
+        }
+        if rule.compress == Some(CompressMode::Gzip) {
+            let mut name = dest_name.into_os_string();
+            name.push(".gz");
+            dest_name = PathBuf::from(name);
+        }
+        let relative_dir = match &rule.preserve_structure_from {
+            Some(base) => match relative_to_base(Path::new(base), &source_file) {
+                Some(relative) => relative,
+                None => {
+                    recorder.note(
+                        LogRecord::new("skipped").paths(&source_file, None),
+                        tr!(Msg::NotUnderStructureBase, source_file.display(), base),
+                    );
+                    continue;
+                }
+            },
+            None => file.relative_path.parent().map(Path::to_path_buf).unwrap_or_default(),
+        };
+        let relative_dir = relative_dir.as_path();
+        let mut dest_file = if rule.flatten && rule.preserve_structure_from.is_none() {
+            if relative_dir != Path::new("") {
+                recorder.log(tr!(Msg::Flattened, file.relative_path.display()));
+            }
+            dest_dir.join(dest_name)
+        } else {
+            dest_dir.join(relative_dir).join(dest_name)
+        };
+        // 同じ実行でフラット化した別のファイルとは、on_conflict によらず番号を付けて両方残す
+        // (dedupe は移動済みのファイルと内容を比較するため除く。並列では転送中で比較できないため除かない)
+        if flattened.contains(&destination_key(&dest_file)) && (rule.on_conflict != ConflictMode::Dedupe || concurrency > 1) {
+            let renamed = next_available_path(&dest_file, &flattened);
+            let warning = tr!(Msg::ConflictRename, dest_file.display(), renamed.display());
+            recorder.log(&warning);
+            recorder.result.warnings.push(warning);
+            dest_file = renamed;
+        }
+        // zip は 1 つのアーカイブにまとめるため、ここではアーカイブ内で名前が重ならないようにするだけにする
+        if rule.compress == Some(CompressMode::Zip) {
+            if source_file.is_dir() {
+                recorder.note(LogRecord::new("error").paths(&source_file, None), tr!(Msg::CompressFolder, source_file.display()));
+                continue;
+            }
+            if zipped.contains(&destination_key(&dest_file)) {
+                dest_file = next_available_path(&dest_file, &zipped);
+            }
+            // 上限に達したら、キャンセルと同様に以降のルールも実行しない
+            let Some(reserved) = reserve_run_limit(state, &source_file) else {
+                recorder.note(LogRecord::new("limit_reached"), tr!(Msg::RunLimitReached, rule.name, total - processed));
+                (stopped, cancelled) = (true, true);
+                continue;
+            };
+            zipped.insert(destination_key(&dest_file));
+            let size = fs::metadata(&source_file).map_or(0, |m| m.len());
+            taken += 1;
+            zip_entries.push((source_file, dest_file, size, reserved));
+            continue;
+        }
+        if dest_file.exists() {
+            match rule.on_conflict {
+                ConflictMode::Overwrite => {
+                    let warning = tr!(Msg::ConflictOverwrite, dest_file.display());
+                    recorder.log(&warning);
+                    recorder.result.warnings.push(warning);
+                }
+                ConflictMode::Skip => {
+                    let warning = tr!(Msg::ConflictSkip, dest_file.display());
+                    recorder.result.skipped.push(SkippedFile::new(&source_file, SkipReason::Conflict, Some(warning.clone())));
+                    recorder.note(LogRecord::new("skipped").paths(&source_file, Some(&dest_file)), warning);
+                    continue;
+                }
+                ConflictMode::KeepNewer => {
+                    let modified = |path: &Path| fs::metadata(path).and_then(|metadata| metadata.modified());
+                    let (source_time, dest_time) = match (modified(&source_file), modified(&dest_file)) {
+                        (Ok(source_time), Ok(dest_time)) => (source_time, dest_time),
+                        (Err(e), _) | (_, Err(e)) => {
+                            recorder.note(
+                                LogRecord::new("error").paths(&source_file, Some(&dest_file)),
+                                tr!(Msg::ModifiedTimeFailed, source_file.display(), dest_file.display(), e),
+                            );
+                            continue;
+                        }
+                    };
+                    let format = |time: SystemTime| DateTime::<Local>::from(time).format("%Y-%m-%d %H:%M:%S").to_string();
+                    let (source_time, dest_time, newer) = (format(source_time), format(dest_time), source_time > dest_time);
+                    if newer {
+                        let warning = tr!(Msg::KeepNewerOverwrite, dest_file.display(), source_time, dest_time);
+                        recorder.log(&warning);
+                        recorder.result.warnings.push(warning);
+                    } else {
+                        let skipped = tr!(Msg::KeepNewerSkip, source_file.display(), source_time, dest_time);
+                        let deleting = rule.delete_older_source && !rule.mode.keeps_source();
+                        let decision = if !deleting {
+                            Ok(skipped)
+                        } else if dry_run {
+                            Ok(format!("{} ({})", skipped, tr!(Msg::OlderSourceDeletePlanned)))
+                        } else {
+                            let deleted = if rule.use_trash {
+                                trash::delete(&source_file).map_err(|e| e.to_string())
+                            } else if source_file.is_dir() {
+                                fs::remove_dir_all(&source_file).map_err(|e| e.to_string())
+                            } else {
+                                fs::remove_file(&source_file).map_err(|e| e.to_string())
+                            };
+                            deleted
+                                .map(|_| format!("{} ({})", skipped, tr!(Msg::OlderSourceDeleted)))
+                                .map_err(|e| tr!(Msg::OlderSourceDeleteFailed, source_file.display(), e))
+                        };
+                        match decision {
+                            Ok(warning) => {
+                                if deleting {
+                                    taken += 1;
+                                }
+                                recorder.result.skipped.push(SkippedFile::new(&source_file, SkipReason::Conflict, Some(warning.clone())));
+                                recorder.note(LogRecord::new("skipped").paths(&source_file, Some(&dest_file)), warning);
+                            }
+                            Err(error) => recorder.note(LogRecord::new("error").paths(&source_file, Some(&dest_file)), error),
+                        }
+                        continue;
+                    }
+                }
+                ConflictMode::Rename | ConflictMode::Dedupe => {
+                    if rule.on_conflict == ConflictMode::Dedupe && !source_file.is_dir() {
+                        match find_duplicate(&source_file, &dest_file) {
+                            Ok(Some(duplicate)) => {
+                                let removed = if rule.mode.keeps_source() {
+                                    Ok(tr!(Msg::DuplicateSkipped, source_file.display(), duplicate.display()))
+                                } else if dry_run {
+                                    Ok(tr!(Msg::DuplicatePlanned, source_file.display(), duplicate.display()))
+                                } else {
+                                    let deleted = if rule.use_trash {
+                                        trash::delete(&source_file).map_err(|e| e.to_string())
+                                    } else {
+                                        fs::remove_file(&source_file).map_err(|e| e.to_string())
+                                    };
+                                    deleted
+                                        .map(|_| tr!(Msg::DuplicateRemoved, source_file.display(), duplicate.display()))
+                                        .map_err(|e| tr!(Msg::DuplicateRemoveFailed, source_file.display(), e))
+                                };
+                                match removed {
+                                    Ok(warning) => {
+                                        taken += 1;
+                                        recorder.note(LogRecord::new("duplicate").paths(&source_file, Some(&duplicate)), warning);
+                                    }
+                                    Err(error) => recorder.note(LogRecord::new("error").paths(&source_file, Some(&duplicate)), error),
+                                }
+                                continue;
+                            }
+                            Ok(None) => {}
+                            Err(e) => {
+                                recorder.note(
+                                    LogRecord::new("error").paths(&source_file, Some(&dest_file)),
+                                    tr!(Msg::DuplicateCheckFailed, source_file.display(), e),
+                                );
+                                continue;
+                            }
+                        }
+                    }
+                    let renamed = next_available_path(&dest_file, &flattened);
+                    let warning = tr!(Msg::ConflictRename, dest_file.display(), renamed.display());
+                    recorder.log(&warning);
+                    recorder.result.warnings.push(warning);
+                    dest_file = renamed;
+                }
+            }
+        }
+        // 上限と keep_at_least は、並列で転送中の分も含めるよう転送を始める時点で数える
+        let Some(reserved) = reserve_run_limit(state, &source_file) else {
+            recorder.note(LogRecord::new("limit_reached"), tr!(Msg::RunLimitReached, rule.name, total - processed));
+            (stopped, cancelled) = (true, true);
+            continue;
+        };
+        // 並列では転送中のファイルがまだ存在しないため、flatten でなくても同じ宛先に重ねて転送しないようにする
+        if rule.flatten || concurrency > 1 {
+            flattened.insert(destination_key(&dest_file));
+        }
+        if dry_run {
+            let (planned, action) = match rule.mode {
+                RuleMode::Move | RuleMode::ByExtension | RuleMode::Alphabetical => (Msg::DryRunPlanned, "would_move"),
+                RuleMode::Copy => (Msg::DryRunCopyPlanned, "would_copy"),
+                RuleMode::Symlink | RuleMode::Hardlink => (Msg::DryRunLinkPlanned, "would_link"),
+            };
+            recorder.log(tr!(planned, source_file.display(), dest_file.display()));
+            recorder.record(LogRecord::new(action).paths(&source_file, Some(&dest_file)));
+            if let Some(claims) = claims.as_deref_mut() {
+                claims.insert(dest_file.clone(), rule.name.clone());
+            }
+            let size = fs::metadata(&source_file).map_or(0, |m| m.len());
+            recorder.result.bytes += size;
+            taken += 1;
+            recorder.result.moved.push(MovedFile::new(&source_file, &dest_file, "dry-run", size));
+            continue;
+        }
+        if let Some(dest_parent) = dest_file.parent() {
+            if !dest_parent.exists() {
+                if let Err(e) = fs::create_dir_all(dest_parent) {
+                    recorder.note(
+                        LogRecord::new("error").paths(&source_file, Some(&dest_file)),
+                        tr!(Msg::DestSubfolderCreateFailed, dest_parent.display(), e),
+                    );
+                    release_run_limit(state, reserved);
+                    continue;
+                }
+            }
+        }
+        let transfer = match (rule.mode, rule.compress) {
+            (RuleMode::Move | RuleMode::ByExtension | RuleMode::Alphabetical, Some(CompressMode::Gzip)) => gzip_move_file,
+            (RuleMode::Copy, Some(CompressMode::Gzip)) => gzip_copy_file,
+            (RuleMode::Move | RuleMode::ByExtension | RuleMode::Alphabetical, _) => move_file,
+            (RuleMode::Copy, _) => copy_file,
+            (RuleMode::Symlink, _) => symlink_file,
+            (RuleMode::Hardlink, _) => hardlink_file,
+        };
+        // 圧縮率を求めるため、元ファイルが消える前にサイズを取っておく
+        let original_size = rule.compress.is_some().then(|| path_size(&source_file));
+        taken += 1;
+        let options = MoveOptions {
+            progress: Some(CopyProgress { app_handle: app_handle.clone(), rule_name: rule.name.clone() }),
+            ..MoveOptions::for_rule(rule)
+        };
+        let retry_count = rule.retry_count.unwrap_or(0);
+        let retry_delay = Duration::from_millis(rule.retry_delay_ms.unwrap_or(DEFAULT_RETRY_DELAY_MS));
+        let job = if concurrency > 1 {
+            // 空きができるまで待ち、転送が終わったら次の転送に譲る
+            let permit = permits.acquire();
+            let (source, dest) = (source_file.clone(), dest_file.clone());
+            let language = i18n::language();
+            TransferJob::Running(std::thread::spawn(move || {
+                let _permit = permit;
+                let _language = i18n::use_language(language);
+                transfer_with_retry(transfer, &source, &dest, options, retry_count, retry_delay)
+            }))
+        } else {
+            TransferJob::Done(transfer_with_retry(transfer, &source_file, &dest_file, options, retry_count, retry_delay))
+        };
+        pending.push_back(PendingTransfer { source_file, dest_file, original_size, reserved, job });
+    }
+    // キャンセルや上限で止めた場合も、上限に数えて集めたファイルはアーカイブに書いてから返す
+    if !zip_entries.is_empty() {
+        let name = format!("{}_{}.zip", rule.name, Local::now().format("%Y%m%d_%H%M%S"));
+        let name = sanitize_file_name(&name, &SanitizeOptions { strip_illegal: true, ..Default::default() });
+        let mut archive = dest_path.join(name);
+        if archive.exists() {
+            archive = next_available_path(&archive, &std::collections::HashSet::new());
+        }
+        if dry_run {
+            recorder.log(tr!(Msg::DryRunArchivePlanned, zip_entries.len(), archive.display()));
+            for (source_file, _, size, _) in zip_entries {
+                recorder.record(LogRecord::new("would_archive").paths(&source_file, Some(&archive)));
+                recorder.result.bytes += size;
+                recorder.result.moved.push(MovedFile::new(&source_file, &archive, "dry-run", size));
+            }
+        } else {
+            let entries: Vec<(&Path, String)> = zip_entries.iter()
+                .map(|(source_file, dest_file, ..)| (source_file.as_path(), zip_entry_name(dest_path, dest_file)))
+                .collect();
+            match write_zip_archive(&archive, &entries) {
+                Ok(compressed_size) => {
+                    let original_size = zip_entries.iter().map(|(_, _, size, _)| size).sum();
+                    let written = CompressedArchive::new(&archive, zip_entries.len(), original_size, compressed_size);
+                    recorder.log(tr!(Msg::ArchiveWritten, archive.display(), written.files, compression_percent(&written)));
+                    recorder.result.archives.push(written);
+                    if let Some(claims) = claims {
+                        claims.insert(archive.clone(), rule.name.clone());
+                    }
+                    // 取り消しでは元ファイルを取り出した後でアーカイブを削除するため、アーカイブを先に記録する
+                    let metadata = fs::metadata(&archive).ok();
+                    let archived = |action| JournalEntry {
+                        source: String::new(),
+                        destination: archive.to_string_lossy().into_owned(),
+                        size: metadata.as_ref().map_or(0, |m| m.len()),
+                        modified: metadata.as_ref().and_then(|m| m.modified().ok()),
+                        action,
+                    };
+                    recorder.journal.push(archived(JournalAction::Remove));
+                    // アーカイブを閉じた後で元ファイルを削除する
+                    for (source_file, dest_file, size, _) in zip_entries {
+                        if !rule.mode.keeps_source() {
+                            let deleted = if rule.use_trash {
+                                trash::delete(&source_file).map_err(|e| e.to_string())
+                            } else {
+                                fs::remove_file(&source_file).map_err(|e| e.to_string())
+                            };
+                            if let Err(e) = deleted {
+                                recorder.note(
+                                    LogRecord::new("error").paths(&source_file, Some(&archive)),
+                                    tr!(Msg::DeleteAfterArchiveFailed, source_file.display(), e),
+                                );
+                                continue;
+                            }
+                            recorder.journal.push(JournalEntry {
+                                source: source_file.to_string_lossy().into_owned(),
+                                ..archived(JournalAction::Unzip { entry: zip_entry_name(dest_path, &dest_file) })
+                            });
+                        }
+                        recorder.record(LogRecord::new("archived").paths(&source_file, Some(&archive)));
+                        recorder.result.bytes += size;
+                        recorder.result.moved.push(MovedFile::new(&source_file, &archive, "zip", size));
+                    }
+                }
+                Err(e) => {
+                    // アーカイブに入らなかったファイルは上限に数えない
+                    for (_, _, _, reserved) in zip_entries {
+                        release_run_limit(state, reserved);
+                    }
+                    recorder.note(LogRecord::new("error").paths(&archive, None), e);
+                }
+            }
+        }
+    }
+    if cancelled {
+        return Ok(recorder.finish(true));
+    }
+    // 元ファイルが残るモードとドライランではフォルダは空にならない
+    if rule.remove_empty_dirs && !dry_run && !rule.mode.keeps_source() {
+        for source_path in &source_paths {
+            // .organizerignore で除外したフォルダは整理の対象外なので、空でも削除しない。
+            // 読めなければ除外の範囲が分からないため削除しない (移動済みの結果は返す)
+            let ignored = match read_ignore_patterns(source_path) {
+                Ok(ignored) => ignored,
+                Err(warning) => {
+                    recorder.note(LogRecord::new("warning"), warning);
+                    continue;
+                }
+            };
+            let mut cleanup = EmptyDirCleanup {
+                keep: dest_path,
+                ignored,
+                with_hidden: rule.remove_empty_dirs_with_hidden,
+                removed: Vec::new(),
+                warnings: Vec::new(),
+            };
+            cleanup.visit(source_path);
+            for dir in cleanup.removed {
+                recorder.log(tr!(Msg::EmptyDirRemoved, dir.display()));
+                recorder.record(LogRecord::new("removed_dir").paths(&dir, None));
+            }
+            for warning in cleanup.warnings {
+                recorder.note(LogRecord::new("warning"), warning);
+            }
+        }
+    }
+    emit_progress(app_handle, &rule.name, total, total, Path::new(""));
+    let summary = if dry_run { Msg::DryRunSummary } else { Msg::MovedSummary };
+    recorder.log(tr!(summary, rule.name, recorder.result.moved.len()));
+    Ok(recorder.finish(false))
+}
+
+/// `path` を移動しても max_files_per_run / max_total_bytes_per_run を超えなければ、その分を上限に数えて数えたサイズを返す
+fn reserve_run_limit(state: &OrganizeState, path: &Path) -> Option<u64> {
+    // サイズの上限があるときだけ、フォルダの中身まで数える
+    let limits_bytes = state.run_limits.lock().unwrap().max_bytes.is_some();
+    let size = if limits_bytes { path_size(path) } else { 0 };
+    state.run_limits.lock().unwrap().try_reserve(size).then_some(size)
+}
+
+/// reserve_run_limit で数えたファイルを移動できなかったときに、数えた分を戻す
+fn release_run_limit(state: &OrganizeState, size: u64) {
+    state.run_limits.lock().unwrap().release(size);
+}
+
+/// どのルールにもマッチしなかったソースフォルダ内のファイルを `destination` に移動する。
+/// ソースフォルダを共有するルールのいずれかが recursive の場合だけサブフォルダも辿る
+fn quarantine_unmatched(
+    state: &OrganizeState,
+    rules: &[OrganizeRule],
+    destination: &Path,
+    consumed: &std::collections::HashSet<PathBuf>,
+    dry_run: bool,
+    logging: Option<LogFormat>,
+) -> Result<RuleRun, String> {
+    let rule_name = tr!(Msg::UnmatchedRuleName);
+    let header = tr!(Msg::UnmatchedHeader, destination.display());
+    let mut recorder = RuleRecorder::new(rule_name.to_string(), header, dry_run, logging);
+    let enabled: Vec<&OrganizeRule> = rules.iter().filter(|rule| rule.enabled != Some(false)).collect();
+    // ソースフォルダごとに、最も深く辿るルールの設定で走査する
+    let depth = |rule: &OrganizeRule| rule.recursive.then(|| rule.max_depth.unwrap_or(u32::MAX));
+    let mut sources: Vec<(&Path, &OrganizeRule)> = Vec::new();
+    for &rule in &enabled {
+        for source in rule.sources() {
+            let source = Path::new(source);
+            match sources.iter_mut().find(|(path, _)| *path == source) {
+                Some(entry) if depth(rule) > depth(entry.1) => entry.1 = rule,
+                Some(_) => {}
+                None => sources.push((source, rule)),
+            }
+        }
+    }
+    // 宛先フォルダの中身はルールが移動したファイルなので、再び移動しない
+    let dest_roots: Vec<PathBuf> = enabled.iter().map(|rule| rule.destination_root())
+        .chain([destination.to_path_buf()])
+        .collect();
+    let mut files = Vec::new();
+    for (source, rule) in sources {
+        // ソースフォルダが無い旨はルール側で警告済み
+        if !source.is_dir() {
+            continue;
+        }
+        let scan = OrganizeRule { include_directories: false, ..rule.clone() };
+        files.extend(collect_source_files(source, &scan)?.into_iter().filter(|file| {
+            !file.path.ancestors().any(|path| consumed.contains(path))
+                && !dest_roots.iter().any(|root| file.path.starts_with(root))
+        }));
+    }
+    let total = files.len();
+    let mut taken = std::collections::HashSet::new();
+    for (processed, file) in files.into_iter().enumerate() {
+        let Some(reserved) = reserve_run_limit(state, &file.path) else {
+            recorder.note(LogRecord::new("limit_reached"), tr!(Msg::RunLimitReached, rule_name, total - processed));
+            break;
+        };
+        let mut dest_file = destination.join(&file.relative_path);
+        if dest_file.exists() || taken.contains(&destination_key(&dest_file)) {
+            dest_file = next_available_path(&dest_file, &taken);
+        }
+        taken.insert(destination_key(&dest_file));
+        if dry_run {
+            recorder.log(tr!(Msg::QuarantinePlanned, file.path.display(), dest_file.display()));
+            recorder.record(LogRecord::new("would_quarantine").paths(&file.path, Some(&dest_file)));
+            let size = fs::metadata(&file.path).map_or(0, |m| m.len());
+            recorder.result.bytes += size;
+            recorder.result.moved.push(MovedFile::new(&file.path, &dest_file, "dry-run", size));
+            continue;
+        }
+        let dest_parent = dest_file.parent().unwrap_or(destination);
+        let moved = match fs::create_dir_all(dest_parent) {
+            Ok(()) => move_file(&file.path, &dest_file, MoveOptions::default()),
+            Err(e) => Err(MoveError::new(tr!(Msg::DestSubfolderCreateFailed, dest_parent.display(), e))),
+        };
+        match moved {
+            Ok(outcome) => {
+                recorder.log(tr!(Msg::Quarantined, outcome.strategy, file.path.display(), dest_file.display()));
+                recorder.record(LogRecord { strategy: Some(outcome.strategy), ..LogRecord::new("quarantined") }
+                    .paths(&file.path, Some(&dest_file)));
+                recorder.result.warnings.extend(outcome.warnings);
+                let metadata = fs::metadata(&dest_file).ok();
+                let size = metadata.as_ref().map_or(0, |m| m.len());
+                recorder.result.bytes += size;
+                recorder.journal.push(JournalEntry {
+                    source: file.path.to_string_lossy().into_owned(),
+                    destination: dest_file.to_string_lossy().into_owned(),
+                    size,
+                    modified: metadata.and_then(|m| m.modified().ok()),
+                    action: JournalAction::Move,
+                });
+                recorder.result.moved.push(MovedFile::new(&file.path, &dest_file, outcome.strategy, size));
+            }
+            Err(e) if e.in_use => {
+                release_run_limit(state, reserved);
+                let warning = tr!(Msg::SkippedInUse, file.path.display(), e.message);
+                recorder.result.skipped.push(SkippedFile::new(&file.path, SkipReason::Locked, Some(warning.clone())));
+                recorder.note(LogRecord::new("skipped").paths(&file.path, Some(&dest_file)), warning);
+            }
+            Err(e) => {
+                release_run_limit(state, reserved);
+                recorder.note(LogRecord::new("error").paths(&file.path, Some(&dest_file)), e.message);
+            }
+        }
+    }
+    let summary = if dry_run { Msg::DryRunSummary } else { Msg::MovedSummary };
+    recorder.log(tr!(summary, rule_name, recorder.result.moved.len()));
+    Ok(recorder.finish(false))
+}
+
+/// match_target に応じて、パターンを照合する文字列を作る。パスの区切りは OS によらず `/` にそろえる
+fn match_subject(rule: &OrganizeRule, file: &SourceFile) -> String {
+    let subject = match rule.match_target {
+        MatchTarget::Filename => return file.file_name.to_string_lossy().into_owned(),
+        MatchTarget::Path => file.relative_path.to_string_lossy(),
+        MatchTarget::Fullpath => file.path.to_string_lossy(),
+    };
+    if cfg!(windows) {
+        subject.replace('\\', "/")
+    } else {
+        subject.into_owned()
+    }
+}
+
+/// pattern・exclude・フィルタを満たすファイルに絞り込む。フォルダごと移動するものの中身は除く
+fn select_matching_files(
+    rule: &OrganizeRule,
+    compiled: &CompiledRule,
+    files: Vec<SourceFile>,
+    modified_since: Option<SystemTime>,
+    log_file: &mut Option<Vec<u8>>,
+    skipped: &mut Vec<SkippedFile>,
+) -> Vec<SourceFile> {
+    let mut matched_files = Vec::new();
+    for file in files {
+        let subject = match_subject(rule, &file);
+        let matched = compiled.regex.is_match(&subject);
+        if let Some(log_file) = log_file.as_mut() {
+            writeln!(log_file, "{}", tr!(Msg::FileMatched, file.relative_path.display(), matched)).ok();
+        }
+        if !matched {
+            skipped.push(SkippedFile::new(&file.path, SkipReason::NoMatch, None));
+            continue;
+        }
+        if compiled.excludes.iter().any(|exclude| exclude.is_match(&subject)) {
+            if let Some(log_file) = log_file.as_mut() {
+                writeln!(log_file, "{}", tr!(Msg::Excluded, file.relative_path.display())).ok();
+            }
+            skipped.push(SkippedFile::new(&file.path, SkipReason::Excluded, None));
+            continue;
+        }
+        if !file.is_dir {
+            let reason = filter_skip_reason(rule, &file.path).or_else(|| {
+                modified_since.and_then(|since| unmodified_reason(&file.path, since)).map(|reason| (SkipReason::FilterAge, reason))
+            });
+            if let Some((kind, reason)) = reason {
+                if let Some(log_file) = log_file.as_mut() {
+                    writeln!(log_file, "{}", tr!(Msg::FileSkipped, file.relative_path.display(), reason)).ok();
+                }
+                skipped.push(SkippedFile::new(&file.path, kind, Some(reason)));
+                continue;
+            }
+        }
+        matched_files.push(file);
+    }
+    // フォルダごと移動するものの中身は個別に移動しない
+    let matched_dirs: Vec<PathBuf> = matched_files.iter()
+        .filter(|file| file.is_dir)
+        .map(|file| file.path.clone())
+        .collect();
+    matched_files.retain(|file| !matched_dirs.iter().any(|dir| file.path != *dir && file.path.starts_with(dir)));
+    matched_files
+}
+
+/// ソースまたは宛先のフォルダが重なる (同一または親子関係にある) ルール同士を同じグループにまとめる。
+/// グループ内はルール順に逐次実行し、別グループとは並列に実行する。
+fn group_rules_by_folder(rules: &[OrganizeRule]) -> Vec<Vec<usize>> {
+    let mut groups: Vec<(Vec<PathBuf>, Vec<usize>)> = Vec::new();
+    for (index, rule) in rules.iter().enumerate() {
+        let mut merged: (Vec<PathBuf>, Vec<usize>) = (
+            rule.sources().map(PathBuf::from).chain([rule.destination_root()])
+                .map(|folder| fs::canonicalize(&folder).unwrap_or(folder))
+                .collect(),
+            vec![index],
+        );
+        let mut i = 0;
+        while i < groups.len() {
+            let overlaps = groups[i].0.iter()
+                .any(|a| merged.0.iter().any(|b| a.starts_with(b) || b.starts_with(a)));
+            if overlaps {
+                let (folders, indices) = groups.remove(i);
+                merged.0.extend(folders);
+                merged.1.extend(indices);
+            } else {
+                i += 1;
+            }
+        }
+        groups.push(merged);
+    }
+    groups.into_iter()
+        .map(|(_, mut indices)| {
+            indices.sort_unstable();
+            indices
+        })
+        .collect()
+}
+
+/// 実行履歴の 1 件
+#[derive(Debug, Serialize, Deserialize)]
+struct RunHistoryEntry {
+    /// 実行日時 ("%Y-%m-%d %H:%M:%S")
+    timestamp: String,
+    config_path: String,
+    dry_run: bool,
+    rules: Vec<RuleHistory>,
+    /// 実行全体が失敗した場合のエラー
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// 実行履歴に残すルールごとの件数
+#[derive(Debug, Serialize, Deserialize)]
+struct RuleHistory {
+    rule_name: String,
+    moved: usize,
+    bytes: u64,
+    warnings: usize,
+    errors: Vec<String>,
+}
+
+impl RunHistoryEntry {
+    fn new(config_path: &str, dry_run: bool, results: &[OrganizeResult], error: Option<String>) -> Self {
+        RunHistoryEntry {
+            timestamp: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            config_path: config_path.to_string(),
+            dry_run,
+            rules: results.iter()
+                .map(|result| RuleHistory {
+                    rule_name: result.rule_name.clone(),
+                    moved: result.moved.len(),
+                    bytes: result.bytes,
+                    warnings: result.warnings.len(),
+                    errors: result.errors.clone(),
+                })
+                .collect(),
+            error,
+        }
+    }
+}
+
+const DEFAULT_HISTORY_KEEP: usize = 200;
+
+fn last_runs_file() -> Result<PathBuf, String> {
+    Ok(app_config_dir()?.join("last_runs.json"))
+}
+
+/// 設定ファイルごとの、前回完了した実行の開始日時
+fn read_last_runs() -> std::collections::BTreeMap<String, SystemTime> {
+    last_runs_file().ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        // 壊れている場合は記録がないものとして全件を対象にする
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn last_run_time(config_path: &str) -> Option<SystemTime> {
+    read_last_runs().remove(config_path)
+}
+
+fn save_last_run_time(config_path: &str, time: SystemTime) -> Result<(), String> {
+    let mut last_runs = read_last_runs();
+    last_runs.insert(config_path.to_string(), time);
+    fs::create_dir_all(app_config_dir()?)
+        .map_err(|e| tr!(Msg::ConfigDirCreateFailed, e))?;
+    let content = serde_json::to_string_pretty(&last_runs)
+        .map_err(|e| tr!(Msg::LastRunWriteFailed, e))?;
+    fs::write(last_runs_file()?, content)
+        .map_err(|e| tr!(Msg::LastRunWriteFailed, e))
+}
+
+fn history_file() -> Result<PathBuf, String> {
+    Ok(app_config_dir()?.join("history.json"))
+}
+
+/// 実行履歴を古い順に読み込む
+fn read_history() -> Result<Vec<RunHistoryEntry>, String> {
+    let history_path = history_file()?;
+    if !history_path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(&history_path)
+        .map_err(|e| tr!(Msg::HistoryReadFailed, e))?;
+    serde_json::from_str(&content)
+        .map_err(|e| tr!(Msg::HistoryParseFailed, e))
+}
+
+/// 実行履歴に 1 件追加し、`keep` 件を超えた古いものを削除する
+fn append_history(entry: RunHistoryEntry, keep: usize) -> Result<(), String> {
+    let mut history = read_history()?;
+    history.push(entry);
+    let excess = history.len().saturating_sub(keep);
+    history.drain(..excess);
+    fs::create_dir_all(app_config_dir()?)
+        .map_err(|e| tr!(Msg::ConfigDirCreateFailed, e))?;
+    let content = serde_json::to_string_pretty(&history)
+        .map_err(|e| tr!(Msg::HistoryWriteFailed, e))?;
+    fs::write(history_file()?, content)
+        .map_err(|e| tr!(Msg::HistoryWriteFailed, e))
+}
+
+/// 実行履歴を新しい順に返す
+#[tauri::command]
+async fn load_history() -> Result<Vec<RunHistoryEntry>, String> {
+    let mut history = read_history()?;
+    history.reverse();
+    Ok(history)
+}
+
+fn journal_file() -> Result<PathBuf, String> {
+    Ok(app_config_dir()?.join("last_organize.json"))
+}
+
+/// 取り消し用の記録を書き出す。記録するものがなければ前回の記録を削除する
+fn write_journal(journal: &[JournalEntry]) -> Result<(), String> {
+    if journal.is_empty() {
+        return match fs::remove_file(journal_file()?) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(tr!(Msg::UndoRecordRemoveFailed, e)),
+            _ => Ok(()),
+        };
+    }
+    let config_dir = app_config_dir()?;
+    fs::create_dir_all(&config_dir)
+        .map_err(|e| tr!(Msg::ConfigDirCreateFailed, e))?;
+    let content = serde_json::to_string_pretty(journal)
+        .map_err(|e| tr!(Msg::JournalSerializeFailed, e))?;
+    fs::write(journal_file()?, content)
+        .map_err(|e| tr!(Msg::JournalWriteFailed, e))
+}
+
+/// 直前の整理で移動したファイルを元の場所へ戻し、コピー・リンク・圧縮で作成したファイルを削除する。
+/// 移動後に変更・削除されたファイルや、元の場所に同名ファイルがあるものはスキップする。
+#[tauri::command]
+async fn undo_last_organize() -> Result<UndoResult, String> {
+    let journal_path = journal_file()?;
+    if !journal_path.exists() {
+        return Err(tr!(Msg::NoUndoRecord).to_string());
+    }
+    let content = fs::read_to_string(&journal_path)
+        .map_err(|e| tr!(Msg::UndoRecordReadFailed, e))?;
+    let journal: Vec<JournalEntry> = serde_json::from_str(&content)
+        .map_err(|e| tr!(Msg::UndoRecordParseFailed, e))?;
+
+    let undo = restore_journal(&journal);
+    // 同じ記録で二重に戻さないよう削除する
+    fs::remove_file(&journal_path)
+        .map_err(|e| tr!(Msg::UndoRecordRemoveFailed, e))?;
+    Ok(undo)
+}
+
+/// 記録した移動を新しいものから順に元へ戻し、作成したファイルを削除する。
+/// 移動後に変更・削除されたファイルや、元の場所に同名ファイルがあるものはスキップする。
+fn restore_journal(journal: &[JournalEntry]) -> UndoResult {
+    let mut undo = UndoResult {
+        restored: Vec::new(),
+        removed: Vec::new(),
+        skipped: Vec::new(),
+    };
+    // 取り出せなかったファイルが残っている zip アーカイブ。元ファイルを失わないよう削除しない
+    let mut kept_archives = std::collections::HashSet::new();
+    // 後から移動したものから順に戻す
+    for entry in journal.iter().rev() {
+        let moved_path = Path::new(&entry.destination);
+        let original_path = Path::new(&entry.source);
+        if entry.action == JournalAction::Remove && kept_archives.contains(&entry.destination) {
+            continue;
+        }
+        // リンクはリンク自体を比べ、リンク先の変更では止めない
+        let metadata = if entry.action == JournalAction::Remove { fs::symlink_metadata(moved_path) } else { fs::metadata(moved_path) };
+        let metadata = match metadata {
+            Ok(metadata) => metadata,
+            Err(_) => {
+                undo.skipped.push(tr!(Msg::UndoDestinationMissing, entry.destination));
+                continue;
+            }
+        };
+        let restored = if metadata.len() != entry.size || metadata.modified().ok() != entry.modified {
+            Err(tr!(Msg::UndoModified, entry.destination))
+        } else if entry.action == JournalAction::Remove {
+            remove_created(moved_path, &metadata).map(|()| None).map_err(|e| tr!(Msg::UndoRemoveFailed, entry.destination, e))
+        } else {
+            restore_entry(entry).map(Some)
+        };
+        match restored {
+            Ok(Some(strategy)) => {
+                remove_sidecar(moved_path, &entry.source);
+                undo.restored.push(MovedFile::new(moved_path, original_path, strategy, entry.size));
+            }
+            Ok(None) => {
+                remove_sidecar(moved_path, &entry.source);
+                undo.removed.push(entry.destination.clone());
+            }
+            Err(message) => {
+                if matches!(entry.action, JournalAction::Unzip { .. }) {
+                    kept_archives.insert(entry.destination.clone());
+                }
+                undo.skipped.push(message);
+            }
+        }
+    }
+    undo
+}
+
+/// 記録した移動先から元の場所へ戻し、戻し方を返す
+fn restore_entry(entry: &JournalEntry) -> Result<&'static str, String> {
+    let moved_path = Path::new(&entry.destination);
+    let original_path = Path::new(&entry.source);
+    if original_path.exists() {
+        return Err(tr!(Msg::UndoOriginalExists, entry.source));
+    }
+    if let Some(parent) = original_path.parent() {
+        fs::create_dir_all(parent).map_err(|e| tr!(Msg::UndoFolderCreateFailed, parent.display(), e))?;
+    }
+    match &entry.action {
+        JournalAction::Gunzip => gunzip_file(moved_path, original_path)
+            .map(|()| "gunzip")
+            .map_err(|e| tr!(Msg::UndoExtractFailed, entry.destination, e)),
+        JournalAction::Unzip { entry: name } => unzip_entry(moved_path, name, original_path)
+            .map(|()| "unzip")
+            .map_err(|e| tr!(Msg::UndoExtractFailed, entry.destination, e)),
+        JournalAction::Move | JournalAction::Remove => move_file(moved_path, original_path, MoveOptions::default())
+            .map(|outcome| outcome.strategy)
+            .map_err(|e| e.message),
+    }
+}
+
+/// 整理で作成したファイル・フォルダ・リンクを削除する (リンク先は削除しない)
+fn remove_created(path: &Path, metadata: &fs::Metadata) -> io::Result<()> {
+    if metadata.is_dir() {
+        fs::remove_dir_all(path)
+    } else if metadata.is_symlink() && cfg!(windows) && path.is_dir() {
+        // Windows ではフォルダへのリンクはフォルダとして削除する
+        fs::remove_dir(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+/// gzip で圧縮した `archive` を `dest` に展開し、日時を戻してから `archive` を削除する
+fn gunzip_file(archive: &Path, dest: &Path) -> io::Result<()> {
+    let extracted = fs::File::open(archive).and_then(|input| {
+        let mut output = fs::File::create(dest)?;
+        io::copy(&mut flate2::read::GzDecoder::new(input), &mut output)?;
+        output.sync_all()
+    });
+    if let Err(e) = extracted {
+        fs::remove_file(dest).ok();
+        return Err(e);
+    }
+    copy_file_times(archive, dest).ok();
+    fs::remove_file(archive)
+}
+
+/// zip アーカイブの `name` を `dest` に取り出す
+fn unzip_entry(archive: &Path, name: &str, dest: &Path) -> io::Result<()> {
+    let extracted = fs::File::open(archive).and_then(|input| {
+        let mut archive = zip::ZipArchive::new(input)?;
+        let mut entry = archive.by_name(name)?;
+        let mut output = fs::File::create(dest)?;
+        io::copy(&mut entry, &mut output)?;
+        output.sync_all()
+    });
+    if extracted.is_err() {
+        fs::remove_file(dest).ok();
+    }
+    extracted
+}
+
+/// `report.pdf` のメタデータを置く `report.pdf.meta.json` のパス
+fn sidecar_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".meta.json");
+    PathBuf::from(name)
+}
+
+/// 移動したファイルの隣にメタデータを書く
+fn write_sidecar(rule_name: &str, moved: &MovedFile) -> Result<(), String> {
+    let path = sidecar_path(Path::new(&moved.destination));
+    let sidecar = Sidecar {
+        rule_name: rule_name.to_string(),
+        source: moved.source.clone(),
+        strategy: moved.strategy.clone(),
+        timestamp: moved.timestamp.clone(),
+    };
+    serde_json::to_string_pretty(&sidecar)
+        .map_err(|e| e.to_string())
+        .and_then(|content| fs::write(&path, content).map_err(|e| e.to_string()))
+        .map_err(|e| tr!(Msg::SidecarWriteFailed, path.display(), e))
+}
+
+/// 取り消しで元へ戻したファイルのメタデータを削除する。同名の別ファイルを消さないよう、移動元が一致するものだけを消す
+fn remove_sidecar(moved_path: &Path, source: &str) {
+    let path = sidecar_path(moved_path);
+    let written_by_move = fs::read_to_string(&path).ok()
+        .and_then(|content| serde_json::from_str::<Sidecar>(&content).ok())
+        .is_some_and(|sidecar| sidecar.source == source);
+    if written_by_move {
+        fs::remove_file(&path).ok();
+    }
+}
+
+fn build_regex(pattern: &str, case_insensitive: bool) -> Result<Regex, String> {
+    RegexBuilder::new(pattern)
+        .case_insensitive(case_insensitive)
+        .build()
+        .map_err(|e| tr!(Msg::InvalidRegex, pattern, e))
+}
+
+/// pattern と exclude をコンパイル済みのルール
+struct CompiledRule {
+    regex: Regex,
+    excludes: Vec<Regex>,
+}
+
+impl CompiledRule {
+    /// ルールのすべてのパターンをコンパイルする。失敗した場合は無効なパターンごとのエラーを返す
+    fn new(rule: &OrganizeRule) -> Result<Self, Vec<String>> {
+        let mut errors = Vec::new();
+        let regex = build_regex(&rule.pattern, rule.case_insensitive)
+            .map_err(|e| errors.push(e))
+            .ok();
+        // exclude も大文字・小文字の扱いは pattern に合わせる
+        let excludes: Vec<Regex> = rule.exclude.iter().flatten()
+            .filter_map(|pattern| {
+                build_regex(pattern, rule.case_insensitive)
+                    .map_err(|e| errors.push(e))
+                    .ok()
+            })
+            .collect();
+        match regex {
+            Some(regex) if errors.is_empty() => Ok(CompiledRule { regex, excludes }),
+            _ => Err(errors),
+        }
+    }
+}
+
+/// 有効なルールのパターンをファイル操作の前にまとめてコンパイルする (無効化されたルールは None)。
+/// 無効な正規表現があれば、最初の 1 つで止めずにすべてを列挙したエラーを返す。
+fn compile_rules(rules: &[OrganizeRule]) -> Result<Vec<Option<CompiledRule>>, String> {
+    let mut compiled = Vec::new();
+    let mut errors = Vec::new();
+    for rule in rules {
+        if rule.enabled == Some(false) {
+            compiled.push(None);
+            continue;
+        }
+        match CompiledRule::new(rule) {
+            Ok(rule) => compiled.push(Some(rule)),
+            Err(rule_errors) => errors.extend(rule_errors.into_iter().map(|e| format!("{}: {}", rule.name, e))),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(tr!(Msg::InvalidPatterns, errors.join("\n")));
+    }
+    Ok(compiled)
+}
+
+/// 宛先フォルダが書き込み可能 (存在しない場合は作成可能) かをファイルを作らずに確認する
+fn check_destination_writable(dest: &Path) -> Result<(), String> {
+    // 存在する最も近い祖先フォルダで判定する
+    let existing = dest.ancestors()
+        .find(|p| p.exists())
+        .ok_or_else(|| tr!(Msg::DestDriveNotFound, dest.display()))?;
+    if !existing.is_dir() {
+        return Err(tr!(Msg::DestNotFolder, existing.display()));
+    }
+    let metadata = fs::metadata(existing)
+        .map_err(|e| tr!(Msg::DestMetadataFailed, existing.display(), e))?;
+    if metadata.permissions().readonly() {
+        return Err(tr!(Msg::DestReadOnly, existing.display()));
+    }
+    Ok(())
+}
+
+/// 拡張子・サイズ・経過日数のフィルタを満たさないファイルについて、スキップ理由を返す
+fn filter_skip_reason(rule: &OrganizeRule, path: &Path) -> Option<(SkipReason, String)> {
+    if let Some(extensions) = &rule.extensions {
+        let extension = path.extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let allowed = extensions.iter()
+            .any(|allowed| allowed.trim_start_matches('.').to_lowercase() == extension);
+        if !allowed {
+            return Some((SkipReason::Excluded, tr!(Msg::ExtensionExcluded, extension)));
+        }
+    }
+    if let Some(content_types) = &rule.content_type {
+        let detected = match infer::get_from_path(path) {
+            Ok(detected) => detected.map(|kind| kind.mime_type()),
+            Err(e) => return Some((SkipReason::Excluded, tr!(Msg::ContentTypeUnavailable, e))),
+        };
+        let allowed = detected.is_some_and(|detected| content_types.iter().any(|allowed| content_type_matches(allowed, detected)));
+        if !allowed {
+            let detected = detected.unwrap_or(tr!(Msg::UnknownContentType));
+            return Some((SkipReason::Excluded, tr!(Msg::ContentTypeExcluded, detected)));
+        }
+    }
+    if rule.min_size.is_some() || rule.max_size.is_some() {
+        let size = match fs::metadata(path) {
+            Ok(metadata) => metadata.len(),
+            Err(e) => return Some((SkipReason::FilterSize, tr!(Msg::SizeUnavailable, e))),
+        };
+        if let Some(min_size) = rule.min_size {
+            if size < min_size {
+                return Some((SkipReason::FilterSize, tr!(Msg::SizeBelowMin, size, min_size)));
+            }
+        }
+        if let Some(max_size) = rule.max_size {
+            if size > max_size {
+                return Some((SkipReason::FilterSize, tr!(Msg::SizeAboveMax, size, max_size)));
+            }
+        }
+    }
+    if rule.min_age_days.is_some() || rule.max_age_days.is_some() {
+        let modified = match fs::metadata(path).and_then(|metadata| metadata.modified()) {
+            Ok(modified) => DateTime::<Local>::from(modified),
+            Err(e) => return Some((SkipReason::FilterAge, tr!(Msg::ModifiedUnavailable, e))),
+        };
+        let age_days = (Local::now() - modified).num_days();
+        if let Some(min_age_days) = rule.min_age_days {
+            if age_days < min_age_days as i64 {
+                return Some((SkipReason::FilterAge, tr!(Msg::AgeBelowMin, age_days, min_age_days)));
+            }
+        }
+        if let Some(max_age_days) = rule.max_age_days {
+            if age_days > max_age_days as i64 {
+                return Some((SkipReason::FilterAge, tr!(Msg::AgeAboveMax, age_days, max_age_days)));
+            }
+        }
+    }
+    None
+}
+
+/// 判定したファイルの種類 `detected` が content_type の `allowed` に当たるか。`image/*` のようにサブタイプを省略できる
+fn content_type_matches(allowed: &str, detected: &str) -> bool {
+    let allowed = allowed.trim();
+    match allowed.strip_suffix("/*") {
+        Some(top) => detected.split('/').next().is_some_and(|detected| detected.eq_ignore_ascii_case(top)),
+        None => allowed.eq_ignore_ascii_case(detected),
+    }
+}
+
+/// `since` 以降に更新されていないファイルについて、スキップ理由を返す
+fn unmodified_reason(path: &Path, since: SystemTime) -> Option<String> {
+    match fs::metadata(path).and_then(|metadata| metadata.modified()) {
+        Ok(modified) if modified > since => None,
+        Ok(_) => Some(tr!(Msg::NotModifiedSinceLastRun, DateTime::<Local>::from(since).format("%Y-%m-%d %H:%M:%S"))),
+        Err(e) => Some(tr!(Msg::ModifiedUnavailable, e)),
+    }
+}
+
+/// rename_template をキャプチャで展開し、宛先フォルダからの相対パスを返す。
+/// 宛先フォルダの外を指すパス (絶対パスや `..`) は拒否する。
+fn expand_rename_template(captures: &Captures, template: &str) -> Result<PathBuf, String> {
+    let mut expanded = String::new();
+    captures.expand(template, &mut expanded);
+    if !is_safe_relative_path(&expanded) {
+        return Err(tr!(Msg::InvalidRenameResult, template, expanded));
+    }
+    Ok(PathBuf::from(expanded))
+}
+
+/// destination_folder の `${name}` / `$1` のうち pattern のグループを指すものをキャプチャで置き換える。
+/// グループがマッチしていない場合や、値がフォルダ名として使えない場合はその理由を Err で返す
+fn expand_destination_template(captures: &Captures, regex: &Regex, destination: &str) -> Result<PathBuf, String> {
+    static PLACEHOLDER: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(r"\$\{([A-Za-z0-9_]+)\}|\$([A-Za-z0-9_]+)").unwrap()
+    });
+    let mut expanded = String::new();
+    let mut last = 0;
+    for caps in PLACEHOLDER.captures_iter(destination) {
+        let whole = caps.get(0).unwrap();
+        let name = caps.get(1).or_else(|| caps.get(2)).map_or("", |m| m.as_str());
+        let group = match name.parse::<usize>() {
+            Ok(index) if index < regex.captures_len() => Some(captures.get(index)),
+            Ok(_) => None,
+            Err(_) if regex.capture_names().flatten().any(|group| group == name) => Some(captures.name(name)),
+            Err(_) => None,
+        };
+        // pattern のグループでなければ (未定義の環境変数など) そのまま残す
+        let Some(group) = group else {
+            continue;
+        };
+        let value = group.map(|m| m.as_str()).ok_or_else(|| tr!(Msg::CaptureGroupMissing, name))?;
+        if value.is_empty() || value == "." || value == ".." || value.contains(['/', '\\']) {
+            return Err(tr!(Msg::InvalidCaptureFolder, name, value));
+        }
+        expanded.push_str(&destination[last..whole.start()]);
+        expanded.push_str(value);
+        last = whole.end();
+    }
+    expanded.push_str(&destination[last..]);
+    Ok(PathBuf::from(expanded))
+}
+
+/// by_extension で使うフォルダ名。拡張子を小文字にしたもの (拡張子がなければ `_noext`)
+fn extension_folder(file_name: &Path) -> String {
+    file_name.extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .filter(|extension| !extension.is_empty())
+        .unwrap_or_else(|| "_noext".to_string())
+}
+
+/// alphabetical で使うフォルダ名。ファイル名の先頭の文字を大文字にしたもの (文字以外で始まる場合は `#`)
+fn initial_folder(file_name: &Path) -> String {
+    let name = file_name.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+    match name.chars().next() {
+        Some(first) if first.is_alphabetic() => first.to_uppercase().collect(),
+        _ => "#".to_string(),
+    }
+}
+
+/// SanitizeOptions に従ってファイル名を整形する。整形後に空になる場合は元の名前を返す
+fn sanitize_file_name(name: &str, options: &SanitizeOptions) -> String {
+    let mut sanitized: String = name.chars()
+        .filter(|c| !options.strip_illegal || !matches!(c, '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*'))
+        .map(|c| if options.replace_spaces && c == ' ' { '_' } else { c })
+        .collect();
+    if options.lowercase {
+        sanitized = sanitized.to_lowercase();
+    }
+    if sanitized.is_empty() {
+        return name.to_string();
+    }
+    sanitized
+}
+
+/// ファイルの更新日時 (取得できなければ作成日時) を `format` で整形したサブフォルダと、使用した日時の種類を返す
+fn date_subfolder_for(path: &Path, format: &str) -> Result<(PathBuf, &'static str), String> {
+    use std::fmt::Write as _;
+
+    let metadata = fs::metadata(path)
+        .map_err(|e| tr!(Msg::MetadataFailed, path.display(), e))?;
+    let (time, time_kind) = match metadata.modified() {
+        Ok(modified) => (modified, tr!(Msg::ModifiedTime)),
+        Err(_) => {
+            let created = metadata.created()
+                .map_err(|e| tr!(Msg::FileTimeUnavailable, path.display(), e))?;
+            (created, tr!(Msg::CreatedTime))
+        }
+    };
+    let datetime: DateTime<Local> = time.into();
+    let mut formatted = String::new();
+    write!(formatted, "{}", datetime.format(format))
+        .map_err(|_| tr!(Msg::InvalidDateFormat, format))?;
+    if !is_safe_relative_path(&formatted) {
+        return Err(tr!(Msg::InvalidDateFolder, format, formatted));
+    }
+    Ok((PathBuf::from(formatted), time_kind))
+}
+
+/// 空でなく、絶対パスや `..` を含まない相対パスかどうか
+fn is_safe_relative_path(path: &str) -> bool {
+    !path.is_empty()
+        && Path::new(path).components().all(|c| matches!(c, std::path::Component::Normal(_)))
+}
+
+/// "confirm-organize" イベントでフロントエンドに確認を求め、confirm_organize の回答を待つ。
+/// キャンセルされた場合は拒否として扱う
+fn wait_for_confirmation(app_handle: &AppHandle, state: &OrganizeState, rule_name: &str, count: usize) -> bool {
+    let (tx, rx) = std::sync::mpsc::channel();
+    let id = state.next_confirmation.fetch_add(1, Ordering::SeqCst);
+    state.pending_confirmations.lock().unwrap().insert(id, tx);
+    let request = ConfirmRequest {
+        id,
+        rule_name: rule_name.to_string(),
+        count,
+    };
+    app_handle.emit("confirm-organize", request).ok();
+    let approved = loop {
+        match rx.recv_timeout(Duration::from_millis(200)) {
+            Ok(approved) => break approved,
+            Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {
+                if state.cancel_requested.load(Ordering::SeqCst) {
+                    break false;
+                }
+            }
+            Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => break false,
+        }
+    };
+    state.pending_confirmations.lock().unwrap().remove(&id);
+    approved
+}
+
+/// 進捗をフロントエンドへ通知する。通知の失敗は整理処理に影響させない
+fn emit_progress(app_handle: &AppHandle, rule_name: &str, processed: usize, total: usize, current_file: &Path) {
+    let progress = OrganizeProgress {
+        rule_name: rule_name.to_string(),
+        processed,
+        total,
+        current_file: current_file.to_string_lossy().into_owned(),
+    };
+    app_handle.emit("organize-progress", progress).ok();
+}
+
+/// `root` 配下のファイルを列挙する。`recursive` が true の場合は `max_depth` までサブフォルダを辿り、
+/// `include_directories` が true の場合はフォルダ自体も候補に含める。
+/// シンボリックリンクは辿らないため、リンクによる循環で無限ループになることはない。
+/// `root` の .organizerignore にマッチする名前のファイル・フォルダ (とフォルダの中身) は含めない。
+/// `include_hidden` が false なら隠しファイル・フォルダ (とフォルダの中身) も含めない。
+fn collect_source_files(root: &Path, rule: &OrganizeRule) -> Result<Vec<SourceFile>, String> {
+    collect_source_files_with_hidden(root, rule).map(|(files, _)| files)
+}
+
+/// collect_source_files と同じ。隠しファイルとして除いたパスも返す
+fn collect_source_files_with_hidden(root: &Path, rule: &OrganizeRule) -> Result<(Vec<SourceFile>, Vec<PathBuf>), String> {
+    let ignored = read_ignore_patterns(root)?;
+    let mut files = Vec::new();
+    let mut hidden = Vec::new();
+    let mut pending = vec![(root.to_path_buf(), 0u32)];
+    while let Some((dir, depth)) = pending.pop() {
+        let entries = fs::read_dir(&dir)
+            .map_err(|e| tr!(Msg::FolderReadFailed, e))?;
+        for entry in entries {
+            let entry = entry.map_err(|e| tr!(Msg::EntryReadFailed, e))?;
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if name == IGNORE_FILE_NAME || ignored.iter().any(|pattern| pattern.matches(&name)) {
+                continue;
+            }
+            if !rule.include_hidden && is_hidden(&entry) {
+                hidden.push(entry.path());
+                continue;
+            }
+            let file_type = entry.file_type().map_err(|e| tr!(Msg::FileTypeFailed, e))?;
+            let path = entry.path();
+            let is_candidate = file_type.is_file() || (file_type.is_dir() && rule.include_directories);
+            if is_candidate {
+                let relative_path = path.strip_prefix(root)
+                    .map(Path::to_path_buf)
+                    .unwrap_or_else(|_| PathBuf::from(entry.file_name()));
+                files.push(SourceFile {
+                    path: path.clone(),
+                    is_dir: file_type.is_dir(),
+                    file_name: entry.file_name(),
+                    relative_path,
+                });
+            }
+            if file_type.is_dir() && rule.recursive && rule.max_depth.is_none_or(|max| depth < max) {
+                pending.push((path, depth + 1));
+            }
+        }
+    }
+    Ok((files, hidden))
+}
+
+/// ソースフォルダに置く、整理の対象外にするファイル名の一覧
+const IGNORE_FILE_NAME: &str = ".organizerignore";
+
+/// `root` の .organizerignore から glob を読み込む。`#` で始まる行と空行は無視する。ファイルが無ければ空
+fn read_ignore_patterns(root: &Path) -> Result<Vec<glob::Pattern>, String> {
+    let path = root.join(IGNORE_FILE_NAME);
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(tr!(Msg::IgnoreFileReadFailed, path.display(), e)),
+    };
+    content.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| glob::Pattern::new(line).map_err(|e| tr!(Msg::InvalidIgnorePattern, path.display(), line, e)))
+        .collect()
+}
+
+/// list_matches が返す 1 件
+#[derive(Debug, Serialize)]
+struct MatchedFile {
+    path: String,
+    is_dir: bool,
+    size: u64,
+    /// 更新日時 ("%Y-%m-%d %H:%M:%S")。取得できなければ null
+    modified: Option<String>,
+}
+
+/// ルールに現在マッチするファイルを列挙する。ファイルの移動や宛先フォルダの作成は一切行わない
+#[tauri::command]
+async fn list_matches(mut rule: OrganizeRule) -> Result<Vec<MatchedFile>, String> {
+    expand_rule_paths(&mut rule);
+    let compiled = CompiledRule::new(&rule).map_err(|errors| errors.join("\n"))?;
+    let mut files = Vec::new();
+    for source in rule.sources() {
+        let source_path = Path::new(source);
+        if !source_path.is_dir() {
+            return Err(tr!(Msg::SourceFolderMissing, source));
+        }
+        files.extend(collect_source_files(source_path, &rule)?);
+    }
+    let matched_files = select_matching_files(&rule, &compiled, files, None, &mut None, &mut Vec::new());
+    Ok(matched_files.into_iter()
+        .map(|file| {
+            let metadata = fs::metadata(&file.path).ok();
+            MatchedFile {
+                path: file.path.to_string_lossy().into_owned(),
+                is_dir: file.is_dir,
+                size: metadata.as_ref().map_or(0, |m| m.len()),
+                modified: metadata.and_then(|m| m.modified().ok())
+                    .map(|modified| DateTime::<Local>::from(modified).format("%Y-%m-%d %H:%M:%S").to_string()),
+            }
+        })
+        .collect())
+}
+
+/// count_source_files が返すルールごとの件数
+#[derive(Debug, Serialize)]
+struct SourceFileCount {
+    rule_name: String,
+    /// ソースフォルダ内の対象 (ファイル、include_directories ならフォルダも) の総数
+    total: usize,
+    /// パターンとフィルタにマッチする数。無効なルールは null
+    matched: Option<usize>,
+    /// 存在しないソースフォルダ
+    missing_sources: Vec<String>,
+}
+
+/// 設定の各ルールについて、ソースフォルダ内のファイル数とマッチする数を数える。ファイルは移動しない
+#[tauri::command]
+async fn count_source_files(config_path: String) -> Result<Vec<SourceFileCount>, String> {
+    let mut config = read_config(&config_path)?;
+    let _language = i18n::use_language(config.language());
+    let compiled = compile_rules(&config.rules)?;
+    let mut counts = Vec::new();
+    for (rule, compiled) in config.rules.iter_mut().zip(&compiled) {
+        expand_rule_paths(rule);
+        let mut files = Vec::new();
+        let mut missing_sources = Vec::new();
+        for source in rule.sources() {
+            let source_path = Path::new(source);
+            if source_path.is_dir() {
+                files.extend(collect_source_files(source_path, rule)?);
+            } else {
+                missing_sources.push(source.to_string());
+            }
+        }
+        let total = files.len();
+        let matched = compiled.as_ref()
+            .map(|compiled| select_matching_files(rule, compiled, files, None, &mut None, &mut Vec::new()).len());
+        counts.push(SourceFileCount {
+            rule_name: rule.name.clone(),
+            total,
+            matched,
+            missing_sources,
+        });
+    }
+    Ok(counts)
+}
+
+/// check_destinations が返すルールごとの宛先の確認結果
+#[derive(Debug, Serialize)]
+struct DestinationCheck {
+    rule_name: String,
+    /// 確認した宛先フォルダ (プレースホルダがあればその手前まで)
+    destination: String,
+    /// 一時ファイルを書いて削除できた
+    writable: bool,
+    /// 宛先フォルダが無かったため作成した
+    created: bool,
+    /// 書き込めない理由
+    error: Option<String>,
+}
+
+/// 設定の各ルールと unmatched_destination の宛先フォルダに一時ファイルを書いて削除し、書き込めるかを確かめる。
+/// 宛先が無ければ create_destination のときだけ作成する (作成したフォルダは残す)。ソースのファイルは移動しない
+#[tauri::command]
+async fn check_destinations(config_path: String) -> Result<Vec<DestinationCheck>, String> {
+    let mut config = read_config(&config_path)?;
+    let _language = i18n::use_language(config.language());
+    let mut targets = Vec::new();
+    for rule in &mut config.rules {
+        expand_rule_paths(rule);
+        let timeout = Duration::from_millis(rule.network_timeout_ms.unwrap_or(DEFAULT_NETWORK_TIMEOUT_MS));
+        targets.push((rule.name.clone(), rule.destination_folder.clone(), rule.destination_root(), rule.create_destination, timeout));
+    }
+    // 未分類のファイルの宛先は実行時に常に作成される
+    if let Some(folder) = &config.unmatched_destination {
+        let folder = expand_path(folder, &[]);
+        let timeout = Duration::from_millis(DEFAULT_NETWORK_TIMEOUT_MS);
+        targets.push((tr!(Msg::UnmatchedRuleName).to_string(), folder.clone(), PathBuf::from(folder), true, timeout));
+    }
+    let mut checks = Vec::new();
+    for (rule_name, folder, destination, create, timeout) in targets {
+        let mut created = false;
+        let mut error = None;
+        // オフラインの共有で応答を待って止まらないよう、先に到達できるかを確かめる
+        if let Some(share) = network_share_root(&folder) {
+            if let Err(reason) = check_reachable(&share, timeout) {
+                error = Some(tr!(Msg::NetworkUnreachable, rule_name, share.display(), reason));
+            }
+        }
+        if error.is_none() && !destination.exists() {
+            if !create {
+                error = Some(tr!(Msg::DestinationMissing, rule_name, destination.display()));
+            } else {
+                match fs::create_dir_all(&destination) {
+                    Ok(()) => created = true,
+                    Err(e) => error = Some(tr!(Msg::DestFolderCreateFailed, e)),
+                }
+            }
+        }
+        if error.is_none() {
+            if let Err(e) = probe_writable(&destination) {
+                error = Some(tr!(Msg::DestinationNotWritable, destination.display(), e));
+            }
+        }
+        checks.push(DestinationCheck {
+            rule_name,
+            destination: destination.to_string_lossy().into_owned(),
+            writable: error.is_none(),
+            created,
+            error,
+        });
+    }
+    Ok(checks)
+}
+
+/// `dir` に一時ファイルを書き、削除できるかを確かめる
+fn probe_writable(dir: &Path) -> io::Result<()> {
+    let path = dir.join(format!(".file-organizer-probe-{}", std::process::id()));
+    let written = fs::File::create(&path).and_then(|mut file| {
+        file.write_all(b"file-organizer")?;
+        file.sync_all()
+    });
+    let removed = fs::remove_file(&path);
+    written?;
+    removed
+}
+
+/// detect_collisions が返す、複数のファイルが移動してくる宛先
+#[derive(Debug, Serialize)]
+struct Collision {
+    destination: String,
+    /// 移動元のパスと、それを対象にするルール名
+    sources: Vec<CollidingSource>,
+    /// 宛先に同名のファイルが既にある
+    exists: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct CollidingSource {
+    rule_name: String,
+    path: String,
+}
+
+/// ファイルの移動先を on_conflict による名前の変更を行う前の形で求める。移動の対象にならないファイルは None
+fn planned_destination(rule: &OrganizeRule, compiled: &CompiledRule, file: &SourceFile) -> Option<PathBuf> {
+    let subject = match_subject(rule, file);
+    let captures = compiled.regex.captures(&subject)?;
+    let mut dest_name = match &rule.rename_template {
+        Some(template) => expand_rename_template(&captures, template).ok()?,
+        None => PathBuf::from(&file.file_name),
+    };
+    let dest_root = rule.destination_root();
+    let mut dest_dir = if dest_root != Path::new(&rule.destination_folder) {
+        expand_destination_template(&captures, &compiled.regex, &rule.destination_folder).ok()?
+    } else {
+        dest_root
+    };
+    match rule.mode {
+        RuleMode::ByExtension => dest_dir.push(extension_folder(&dest_name)),
+        RuleMode::Alphabetical => dest_dir.push(initial_folder(&dest_name)),
+        _ => {}
+    }
+    if let Some(format) = &rule.date_subfolder {
+        dest_dir.push(date_subfolder_for(&file.path, format).ok()?.0);
+    }
+    if let (Some(options), Some(name)) = (&rule.sanitize, dest_name.file_name()) {
+        let sanitized = sanitize_file_name(&name.to_string_lossy(), options);
+        dest_name.set_file_name(sanitized);
+    }
+    if rule.compress == Some(CompressMode::Gzip) {
+        let mut name = dest_name.into_os_string();
+        name.push(".gz");
+        dest_name = PathBuf::from(name);
+    }
+    if let Some(base) = &rule.preserve_structure_from {
+        return Some(dest_dir.join(relative_to_base(Path::new(base), &file.path)?).join(dest_name));
+    }
+    if rule.flatten {
+        return Some(dest_dir.join(dest_name));
+    }
+    let relative_dir = file.relative_path.parent().unwrap_or_else(|| Path::new(""));
+    Some(dest_dir.join(relative_dir).join(dest_name))
+}
+
+/// preserve_structure_from の `base` から `path` の親フォルダまでの相対パス。`base` の外なら None
+fn relative_to_base(base: &Path, path: &Path) -> Option<PathBuf> {
+    let parent = path.parent()?;
+    if let Ok(relative) = parent.strip_prefix(base) {
+        return Some(relative.to_path_buf());
+    }
+    // シンボリックリンクや `..` などによる表記の違いを吸収して比べる
+    let (base, parent) = (fs::canonicalize(base).ok()?, fs::canonicalize(parent).ok()?);
+    parent.strip_prefix(&base).ok().map(Path::to_path_buf)
+}
+
+/// 設定のすべてのルールについて移動先を求め、複数のファイルが同じパスに移動する宛先を返す。ファイルは移動しない
+#[tauri::command]
+async fn detect_collisions(config_path: String) -> Result<Vec<Collision>, String> {
+    let mut config = read_config(&config_path)?;
+    let _language = i18n::use_language(config.language());
+    config.sort_rules_by_priority();
+    let compiled = compile_rules(&config.rules)?;
+    // Windows では大文字と小文字だけが違う宛先も同じものとしてまとめ、最初に見つかった表記で返す
+    let mut destinations: std::collections::BTreeMap<PathBuf, (PathBuf, Vec<CollidingSource>)> = Default::default();
+    // first_match_wins では、先のルールが対象にしたファイルを後のルールは扱わない
+    let mut claimed = std::collections::HashSet::new();
+    for (rule, compiled) in config.rules.iter_mut().zip(&compiled) {
+        let Some(compiled) = compiled else {
+            continue;
+        };
+        expand_rule_paths(rule);
+        let mut files = Vec::new();
+        for source in rule.sources() {
+            let source_path = Path::new(source);
+            if source_path.is_dir() {
+                files.extend(collect_source_files(source_path, rule)?);
+            }
+        }
+        for file in select_matching_files(rule, compiled, files, None, &mut None, &mut Vec::new()) {
+            if config.first_match_wins && !claimed.insert(file.path.clone()) {
+                continue;
+            }
+            let Some(destination) = planned_destination(rule, compiled, &file) else {
+                continue;
+            };
+            let source = CollidingSource {
+                rule_name: rule.name.clone(),
+                path: file.path.to_string_lossy().into_owned(),
+            };
+            destinations.entry(destination_key(&destination))
+                .or_insert_with(|| (destination, Vec::new()))
+                .1
+                .push(source);
+        }
+    }
+    Ok(destinations.into_values()
+        .filter(|(_, sources)| sources.len() > 1)
+        .map(|(destination, sources)| Collision {
+            exists: destination.exists(),
+            destination: destination.to_string_lossy().into_owned(),
+            sources,
+        })
+        .collect())
+}
+
+/// diff_preview の結果。ファイルは移動元のパスで比較する
+#[derive(Debug, Serialize)]
+struct DiffPreview {
+    /// 設定 A / B をそれぞれドライランしたときの移動予定
+    moves_a: Vec<MovedFile>,
+    moves_b: Vec<MovedFile>,
+    /// A だけ・B だけ・両方が移動するファイル
+    only_a: Vec<String>,
+    only_b: Vec<String>,
+    both: Vec<String>,
+}
+
+/// 設定をドライランで実行し、移動予定のファイルを返す。ログ・履歴・前回実行日時は書かない
+async fn preview_moves(app_handle: &AppHandle, config_path: String) -> Result<Vec<MovedFile>, String> {
+    let mut config = read_config(&config_path)?;
+    let _language = i18n::use_language(config.language());
+    config.sort_rules_by_priority();
+    let compiled = compile_rules(&config.rules)?;
+    for rule in &mut config.rules {
+        expand_rule_paths(rule);
+    }
+    if let Some(folder) = config.unmatched_destination.as_mut() {
+        *folder = expand_path(folder, &[]);
+    }
+    let state = OrganizeState::default();
+    *state.run_limits.lock().unwrap() = config.run_limits();
+    let mut claims = config.first_match_wins.then(ClaimedFiles::new);
+    let mut consumed = std::collections::HashSet::new();
+    let mut moves = Vec::new();
+    for (rule, compiled) in config.rules.iter().zip(&compiled) {
+        let Some(compiled) = compiled else {
+            continue;
+        };
+        let run = run_rule(app_handle, &state, rule, compiled, true, None, claims.as_mut())?;
+        consumed.extend(run.matched);
+        moves.extend(run.result.moved);
+    }
+    if let Some(folder) = &config.unmatched_destination {
+        let run = quarantine_unmatched(&state, &config.rules, Path::new(folder), &consumed, true, None)?;
+        moves.extend(run.result.moved);
+    }
+    Ok(moves)
+}
+
+/// preview_tree で返すフォルダ
+#[derive(Debug, Default, Serialize)]
+struct PreviewFolder {
+    /// フォルダ名。最上位では既存の親フォルダまでをまとめたパス
+    name: String,
+    path: String,
+    /// 既に存在するフォルダか (false なら実行時に作成される)
+    exists: bool,
+    folders: Vec<PreviewFolder>,
+    files: Vec<PreviewFile>,
+}
+
+/// preview_tree で返す、フォルダに置かれる予定のファイル
+#[derive(Debug, Serialize)]
+struct PreviewFile {
+    name: String,
+    source: String,
+}
+
+impl PreviewFolder {
+    /// `destination` に置かれるファイルを、途中のフォルダを作りながら追加する
+    fn insert(&mut self, destination: &Path, source: String) {
+        let mut folder = self;
+        let mut path = PathBuf::new();
+        for component in destination.parent().into_iter().flat_map(Path::components) {
+            path.push(component);
+            let name = component.as_os_str().to_string_lossy().into_owned();
+            let index = match folder.folders.iter().position(|child| child.name == name) {
+                Some(index) => index,
+                None => {
+                    folder.folders.push(PreviewFolder {
+                        name,
+                        path: path.to_string_lossy().into_owned(),
+                        exists: path.is_dir(),
+                        ..Default::default()
+                    });
+                    folder.folders.len() - 1
+                }
+            };
+            folder = &mut folder.folders[index];
+        }
+        let name = destination.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+        folder.files.push(PreviewFile { name, source });
+    }
+
+    /// フォルダとファイルを名前順に並べる
+    fn sort(&mut self) {
+        self.folders.sort_by(|a, b| a.name.cmp(&b.name));
+        self.files.sort_by(|a, b| a.name.cmp(&b.name));
+        for folder in &mut self.folders {
+            folder.sort();
+        }
+    }
+
+    /// ファイルが無く既存の子フォルダが 1 つだけのフォルダをたどり、最上位のフォルダを宛先に近いところから始める
+    fn collapse(mut self) -> Self {
+        while self.files.is_empty() && self.folders.len() == 1 && self.folders[0].exists {
+            let Some(child) = self.folders.pop() else {
+                break;
+            };
+            self = child;
+        }
+        self.name = self.path.clone();
+        self
+    }
+}
+
+/// 設定をドライランし、移動予定のファイルを移動先のフォルダ構成として返す。ファイルは移動しない
+#[tauri::command]
+async fn preview_tree(app_handle: AppHandle, config_path: String) -> Result<Vec<PreviewFolder>, String> {
+    let moves = preview_moves(&app_handle, config_path).await?;
+    Ok(destination_tree(&moves))
+}
+
+/// 移動先のパスからフォルダ構成を組み立てる
+fn destination_tree(moves: &[MovedFile]) -> Vec<PreviewFolder> {
+    let mut root = PreviewFolder::default();
+    for moved in moves {
+        root.insert(Path::new(&moved.destination), moved.source.clone());
+    }
+    root.sort();
+    root.folders.into_iter().map(PreviewFolder::collapse).collect()
+}
+
+/// simulate_config の結果。パスは一時フォルダのものから元のフォルダのものに戻してある
+#[derive(Debug, Serialize)]
+struct SimulationResult {
+    results: Vec<OrganizeResult>,
+    /// 移動したファイルの移動先のフォルダ構成
+    tree: Vec<PreviewFolder>,
+}
+
+/// 一時フォルダにソースと宛先のファイルを空のファイル (更新日時は元のもの) として再現し、設定をドライランではなく実際に実行する。
+/// 元のファイルには触れず、一時フォルダは最後に削除する。ログ・履歴・前回実行日時は書かない。
+/// 中身は再現しないため、サイズの条件と dedupe の内容の比較は元のファイルと結果が異なることがある
+#[tauri::command]
+async fn simulate_config(app_handle: AppHandle, config_path: String) -> Result<SimulationResult, String> {
+    let mut config = read_config(&config_path)?;
+    let _language = i18n::use_language(config.language());
+    config.sort_rules_by_priority();
+    let compiled = compile_rules(&config.rules)?;
+    for rule in &mut config.rules {
+        expand_rule_paths(rule);
+    }
+    if let Some(folder) = config.unmatched_destination.as_mut() {
+        *folder = expand_path(folder, &[]);
+    }
+    let root = std::env::temp_dir().join(format!(
+        "file-organizer-sandbox-{}-{}",
+        std::process::id(),
+        Local::now().format("%Y%m%d%H%M%S%f"),
+    ));
+    fs::create_dir_all(&root).map_err(|e| tr!(Msg::SandboxCreateFailed, e))?;
+    let mut sandbox = Sandbox { root, roots: Vec::new() };
+    let simulated = simulate_in_sandbox(&app_handle, &mut sandbox, config, &compiled);
+    fs::remove_dir_all(&sandbox.root).ok();
+    simulated
+}
+
+fn simulate_in_sandbox(
+    app_handle: &AppHandle,
+    sandbox: &mut Sandbox,
+    mut config: Config,
+    compiled: &[Option<CompiledRule>],
+) -> Result<SimulationResult, String> {
+    for (rule, compiled) in config.rules.iter_mut().zip(compiled) {
+        if compiled.is_none() {
+            continue;
+        }
+        // ごみ箱はサンドボックスの外にあるため、サンドボックスのファイルはごみ箱に送らずに削除する
+        rule.use_trash = false;
+        let recursive = rule.recursive;
+        for source in rule.sources_mut() {
+            sandbox.reproduce(Path::new(source), recursive)?;
+            *source = sandbox.path(Path::new(source)).to_string_lossy().into_owned();
+        }
+        if let Some(base) = rule.preserve_structure_from.as_mut() {
+            *base = sandbox.path(Path::new(base)).to_string_lossy().into_owned();
+        }
+        // 既存のファイルとの衝突も再現する
+        sandbox.reproduce(&rule.destination_root(), true)?;
+        rule.destination_folder = sandbox.path(Path::new(&rule.destination_folder)).to_string_lossy().into_owned();
+    }
+    if let Some(folder) = config.unmatched_destination.as_mut() {
+        sandbox.reproduce(Path::new(folder), true)?;
+        *folder = sandbox.path(Path::new(folder)).to_string_lossy().into_owned();
+    }
+
+    let state = OrganizeState::default();
+    *state.run_limits.lock().unwrap() = config.run_limits();
+    let mut claims = config.first_match_wins.then(ClaimedFiles::new);
+    let mut consumed = std::collections::HashSet::new();
+    let mut results = Vec::new();
+    for (rule, compiled) in config.rules.iter().zip(compiled) {
+        let Some(compiled) = compiled else {
+            continue;
+        };
+        let run = run_rule(app_handle, &state, rule, compiled, false, None, claims.as_mut())?;
+        consumed.extend(run.matched);
+        results.push(run.result);
+        if run.cancelled {
+            break;
+        }
+    }
+    if let Some(folder) = &config.unmatched_destination {
+        let run = quarantine_unmatched(&state, &config.rules, Path::new(folder), &consumed, false, None)?;
+        results.push(run.result);
+    }
+
+    for result in &mut results {
+        for moved in &mut result.moved {
+            moved.source = sandbox.original(&moved.source);
+            moved.destination = sandbox.original(&moved.destination);
+        }
+        for archive in &mut result.archives {
+            archive.path = sandbox.original(&archive.path);
+        }
+        for skipped in &mut result.skipped {
+            skipped.path = sandbox.original(&skipped.path);
+            if let Some(message) = skipped.message.as_mut() {
+                *message = sandbox.original_in_message(message);
+            }
+        }
+        for message in result.warnings.iter_mut().chain(&mut result.errors) {
+            *message = sandbox.original_in_message(message);
+        }
+    }
+    let moves: Vec<MovedFile> = results.iter().flat_map(|result| result.moved.iter().cloned()).collect();
+    Ok(SimulationResult { results, tree: destination_tree(&moves) })
+}
+
+/// simulate_config の一時フォルダ。元のパスのドライブ (ルート) ごとに一時フォルダの下のフォルダを割り当てる
+struct Sandbox {
+    root: PathBuf,
+    /// (元のルート, 一時フォルダの下の対応するフォルダ)
+    roots: Vec<(PathBuf, PathBuf)>,
+}
+
+impl Sandbox {
+    /// 元のパスに対応する一時フォルダの下のパス
+    fn path(&mut self, original: &Path) -> PathBuf {
+        let original = std::path::absolute(original).unwrap_or_else(|_| original.to_path_buf());
+        let root: PathBuf = original.components()
+            .take_while(|component| matches!(component, std::path::Component::Prefix(_) | std::path::Component::RootDir))
+            .collect();
+        let relative = original.strip_prefix(&root).unwrap_or(&original);
+        let sandboxed_root = match self.roots.iter().find(|(existing, _)| *existing == root) {
+            Some((_, sandboxed)) => sandboxed.clone(),
+            None => {
+                let sandboxed = self.root.join(format!("root{}", self.roots.len()));
+                self.roots.push((root, sandboxed.clone()));
+                sandboxed
+            }
+        };
+        sandboxed_root.join(relative)
+    }
+
+    /// 一時フォルダの下のパスを元のパスに戻す
+    fn original(&self, sandboxed: &str) -> String {
+        let path = Path::new(sandboxed);
+        self.roots.iter()
+            .find_map(|(root, sandboxed_root)| Some(root.join(path.strip_prefix(sandboxed_root).ok()?)))
+            .map_or_else(|| sandboxed.to_string(), |path| path.to_string_lossy().into_owned())
+    }
+
+    /// 警告やエラーのメッセージに含まれる一時フォルダのパスを元のパスに戻す
+    fn original_in_message(&self, message: &str) -> String {
+        let mut message = message.to_string();
+        for (root, sandboxed_root) in &self.roots {
+            // ルール名などに含まれない区切り文字付きで置き換える
+            let sandboxed = format!("{}{}", sandboxed_root.display(), std::path::MAIN_SEPARATOR);
+            message = message.replace(&sandboxed, &root.to_string_lossy());
+        }
+        message
+    }
+
+    /// `original` の中身を、一時フォルダの下に空のファイルとして再現する。存在しないフォルダは何もしない
+    fn reproduce(&mut self, original: &Path, recursive: bool) -> Result<(), String> {
+        if !original.is_dir() {
+            return Ok(());
+        }
+        let target = self.path(original);
+        let failed = |e: io::Error| tr!(Msg::SandboxReproduceFailed, original.display(), e);
+        fs::create_dir_all(&target).map_err(failed)?;
+        for entry in fs::read_dir(original).map_err(failed)? {
+            let entry = entry.map_err(failed)?;
+            let path = entry.path();
+            let dest = target.join(entry.file_name());
+            if entry.file_type().is_ok_and(|file_type| file_type.is_dir()) {
+                if recursive {
+                    self.reproduce(&path, true)?;
+                } else {
+                    fs::create_dir_all(&dest).map_err(failed)?;
+                }
+                continue;
+            }
+            // 除外の設定は中身が必要なのでそのままコピーする
+            if entry.file_name() == IGNORE_FILE_NAME {
+                fs::copy(&path, &dest).map_err(failed)?;
+                continue;
+            }
+            fs::File::create(&dest).map_err(failed)?;
+            // 経過日数の条件と date_subfolder のため、更新日時は元のものにする
+            if let Ok(metadata) = entry.metadata() {
+                filetime::set_file_mtime(&dest, FileTime::from_last_modification_time(&metadata)).ok();
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 2 つの設定をそれぞれドライランし、移動予定のファイルを比べる。ファイルは移動しない
+#[tauri::command]
+async fn diff_preview(app_handle: AppHandle, config_a: String, config_b: String) -> Result<DiffPreview, String> {
+    let moves_a = preview_moves(&app_handle, config_a).await?;
+    let moves_b = preview_moves(&app_handle, config_b).await?;
+    let sources_a: std::collections::BTreeSet<&String> = moves_a.iter().map(|moved| &moved.source).collect();
+    let sources_b: std::collections::BTreeSet<&String> = moves_b.iter().map(|moved| &moved.source).collect();
+    let only_a = sources_a.difference(&sources_b).map(|source| source.to_string()).collect();
+    let only_b = sources_b.difference(&sources_a).map(|source| source.to_string()).collect();
+    let both = sources_a.intersection(&sources_b).map(|source| source.to_string()).collect();
+    Ok(DiffPreview { moves_a, moves_b, only_a, only_b, both })
+}
+
+/// 整理結果を CSV (rule, source, destination, status, size, timestamp) に書き出し、書き出した件数を返す。
+/// `results` を省略すると、最後に実行した整理の結果を使う
+#[tauri::command]
+async fn export_results(
+    state: tauri::State<'_, OrganizeState>,
+    results: Option<Vec<OrganizeResult>>,
+    path: String,
+) -> Result<usize, String> {
+    let results = match results {
+        Some(results) => results,
+        None => state.last_results.lock().unwrap().clone(),
+    };
+    if results.is_empty() {
+        return Err(tr!(Msg::NoResultsToExport).to_string());
+    }
+    let mut writer = csv::Writer::from_path(&path).map_err(|e| tr!(Msg::ExportFailed, e))?;
+    writer.write_record(["rule", "source", "destination", "status", "size", "timestamp"])
+        .map_err(|e| tr!(Msg::ExportFailed, e))?;
+    let mut count = 0;
+    for result in &results {
+        for moved in &result.moved {
+            let status = match moved.strategy.as_str() {
+                _ if result.dry_run => "planned",
+                "copy" => "copied",
+                "symlink" | "hardlink" => "linked",
+                "gzip" | "gzip+trash" | "zip" => "compressed",
+                _ => "moved",
+            };
+            let size = moved.size.to_string();
+            writer.write_record([
+                result.rule_name.as_str(),
+                moved.source.as_str(),
+                moved.destination.as_str(),
+                status,
+                size.as_str(),
+                moved.timestamp.as_str(),
+            ])
+                .map_err(|e| tr!(Msg::ExportFailed, e))?;
+            count += 1;
+        }
+    }
+    writer.flush().map_err(|e| tr!(Msg::ExportFailed, e))?;
+    Ok(count)
+}
+
+/// confirm_threshold による確認に回答し、待機中のルールを再開 (または中止) する
+#[tauri::command]
+async fn confirm_organize(
+    state: tauri::State<'_, OrganizeState>,
+    id: u64,
+    approved: bool,
+) -> Result<(), String> {
+    let pending = state.pending_confirmations.lock().unwrap();
+    let sender = pending.get(&id)
+        .ok_or_else(|| tr!(Msg::NoPendingConfirmation, id))?;
+    sender.send(approved).ok();
+    Ok(())
+}
+
+#[tauri::command]
+async fn cancel_organize(state: tauri::State<'_, OrganizeState>) -> Result<(), String> {
+    state.cancel_requested.store(true, Ordering::SeqCst);
+    Ok(())
+}
+
+/// 設定のソースフォルダを監視し、ファイルが追加されたら該当ルールを自動で実行する。
+/// 監視を開始したフォルダの一覧を返す。既に監視中の場合は新しい設定で置き換える。
+#[tauri::command]
+async fn start_watching(
+    app_handle: AppHandle,
+    state: tauri::State<'_, WatchState>,
+    config_path: String,
+) -> Result<Vec<String>, String> {
+    let mut config = read_config(&config_path)?;
+    let _language = i18n::use_language(config.language());
+    config.sort_rules_by_priority();
+    config.rules.retain(|rule| rule.enabled != Some(false));
+    let compiled = compile_rules(&config.rules)?;
+    for rule in &mut config.rules {
+        expand_rule_paths(rule);
+        // 通知されるパスと比較できるよう正規化しておく
+        for source in rule.sources_mut() {
+            if let Ok(canonical) = fs::canonicalize(&*source) {
+                *source = canonical.to_string_lossy().into_owned();
+            }
+        }
+    }
+    let mut folders: Vec<(String, RecursiveMode)> = Vec::new();
+    for rule in &config.rules {
+        let mode = if rule.recursive { RecursiveMode::Recursive } else { RecursiveMode::NonRecursive };
+        for source in rule.sources() {
+            if !Path::new(source).is_dir() {
+                continue;
+            }
+            match folders.iter_mut().find(|(folder, _)| folder == source) {
+                Some(existing) if mode == RecursiveMode::Recursive => existing.1 = mode,
+                Some(_) => {}
+                None => folders.push((source.clone(), mode)),
+            }
+        }
+    }
+    if folders.is_empty() {
+        return Err(tr!(Msg::NoWatchableFolders).to_string());
+    }
+
+    let handler_app = app_handle.clone();
+    let mut debouncer = new_debouncer(WATCH_DEBOUNCE, move |events: DebounceEventResult| {
+        if let Ok(events) = events {
+            handle_watch_events(&handler_app, &config_path, &config, &compiled, events);
+        }
+    }).map_err(|e| tr!(Msg::WatchStartFailed, e))?;
+    for (folder, mode) in &folders {
+        debouncer.watcher().watch(Path::new(folder), *mode)
+            .map_err(|e| tr!(Msg::WatchFolderFailed, folder, e))?;
+    }
+    *state.debouncer.lock().map_err(|e| e.to_string())? = Some(debouncer);
+    Ok(folders.into_iter().map(|(folder, _)| folder).collect())
+}
+
+#[tauri::command]
+async fn stop_watching(state: tauri::State<'_, WatchState>) -> Result<(), String> {
+    state.debouncer.lock().map_err(|e| e.to_string())?.take();
+    Ok(())
+}
+
+/// 設定の schedule の日時に、その設定で整理を自動実行する。次に実行する日時を返す。
+/// 既にスケジュール中の場合は新しい設定で置き換える
+#[tauri::command]
+async fn start_schedule(
+    app_handle: AppHandle,
+    state: tauri::State<'_, ScheduleState>,
+    config_path: String,
+) -> Result<String, String> {
+    let config = read_config(&config_path)?;
+    let _language = i18n::use_language(config.language());
+    let expression = config.schedule.ok_or_else(|| tr!(Msg::NoSchedule).to_string())?;
+    let schedule = parse_schedule(&expression)?;
+    let next = schedule.upcoming(Local).next().ok_or_else(|| tr!(Msg::ScheduleNeverRuns, expression))?;
+    let stop = std::sync::Arc::new(tokio::sync::Notify::new());
+    if let Some(previous) = state.stop.lock().unwrap().replace(stop.clone()) {
+        previous.notify_one();
+    }
+    let running = state.running.clone();
+    tauri::async_runtime::spawn(run_schedule(app_handle, config_path, schedule, stop, running));
+    Ok(next.format("%Y-%m-%d %H:%M:%S").to_string())
+}
+
+#[tauri::command]
+async fn stop_schedule(state: tauri::State<'_, ScheduleState>) -> Result<(), String> {
+    if let Some(stop) = state.stop.lock().unwrap().take() {
+        stop.notify_one();
+    }
+    Ok(())
+}
+
+/// cron 式を解釈する。秒を省いた 5 項目の式は 0 秒として扱う
+fn parse_schedule(expression: &str) -> Result<cron::Schedule, String> {
+    let expression = expression.trim();
+    let full = if expression.split_whitespace().count() == 5 {
+        format!("0 {}", expression)
+    } else {
+        expression.to_string()
+    };
+    full.parse().map_err(|e| tr!(Msg::InvalidSchedule, expression, e))
+}
+
+/// `stop` が通知されるまで、スケジュールの日時ごとに整理を実行し、scheduled-run イベントで通知する。
+/// 次の日時は実行が終わってから求めるため、実行が長引いて過ぎた日時は飛ばし、実行が積み重なることはない
+async fn run_schedule(
+    app_handle: AppHandle,
+    config_path: String,
+    schedule: cron::Schedule,
+    stop: std::sync::Arc<tokio::sync::Notify>,
+    running: std::sync::Arc<tokio::sync::Mutex<()>>,
+) {
+    while let Some(next) = schedule.upcoming(Local).next() {
+        let wait = (next - Local::now()).to_std().unwrap_or_default();
+        tokio::select! {
+            _ = tokio::time::sleep(wait) => {}
+            _ = stop.notified() => return,
+        }
+        let _running = running.lock().await;
+        let started_at = Local::now();
+        let config = read_config(&config_path);
+        let mut log_file = config.as_ref().ok().and_then(|config| open_log_file(config).ok().flatten());
+        let format = config.as_ref().map_or(LogFormat::Text, |config| config.log_format);
+        if let Some(ref mut log_file) = log_file {
+            let _language = config.as_ref().ok().map(|config| i18n::use_language(config.language()));
+            match format {
+                LogFormat::Text => {
+                    writeln!(log_file, "{}", tr!(Msg::ScheduledRun, started_at.format("%Y-%m-%d %H:%M:%S"), config_path)).ok();
+                }
+                LogFormat::Json => LogRecord::new("scheduled").message(&config_path).write(log_file),
+            }
+        }
+        // 手動実行とキャンセルや上限を取り合わないよう、実行ごとに独立した状態で実行する
+        let state = OrganizeState::default();
+        state.unattended.store(true, Ordering::SeqCst);
+        let report = organize(app_handle.clone(), &state, config_path.clone(), false, None).await;
+        let (moved, error) = match report {
+            Ok(report) => (report.summary.total_moved, None),
+            Err(e) => {
+                if let Some(ref mut log_file) = log_file {
+                    write_log_error(log_file, format, &e);
+                }
+                (0, Some(e))
+            }
+        };
+        let run = ScheduledRun {
+            config_path: config_path.clone(),
+            started_at: started_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+            moved,
+            error,
+            next_run: schedule.upcoming(Local).next().map(|next| next.format("%Y-%m-%d %H:%M:%S").to_string()),
+        };
+        app_handle.emit("scheduled-run", run).ok();
+    }
+}
+
+/// 監視中に変更があったフォルダを対象とするルールを実行し、移動があれば auto-organized イベントで通知する
+fn handle_watch_events(
+    app_handle: &AppHandle,
+    config_path: &str,
+    config: &Config,
+    compiled: &[Option<CompiledRule>],
+    events: Vec<DebouncedEvent>,
+) {
+    let _language = i18n::use_language(config.language());
+    let changed: Vec<PathBuf> = events.into_iter()
+        .map(|event| event.path)
+        .filter(|path| path.is_file())
+        .collect();
+    if changed.is_empty() {
+        return;
+    }
+    // 手動実行のキャンセル要求の影響を受けないよう、独立した状態で実行する。実行の上限はイベントごとに数える
+    let state = OrganizeState::default();
+    *state.confirm_threshold.lock().unwrap() = config.confirm_threshold.filter(|_| !config.auto_confirm);
+    *state.modified_since.lock().unwrap() = if config.incremental { last_run_time(config_path) } else { None };
+    state.unattended.store(true, Ordering::SeqCst);
+    state.verbose_log.store(config.verbose_log, Ordering::SeqCst);
+    *state.run_limits.lock().unwrap() = config.run_limits();
+    let mut claims = config.first_match_wins.then(ClaimedFiles::new);
+    let mut log_file = open_log_file(config).ok().flatten();
+    let logging = log_file.is_some().then_some(config.log_format);
+    for (rule, compiled) in config.rules.iter().zip(compiled) {
+        let Some(compiled) = compiled else {
+            continue;
+        };
+        let affected = rule.sources().map(Path::new).any(|source| {
+            changed.iter().any(|path| {
+                if rule.recursive { path.starts_with(source) } else { path.parent() == Some(source) }
+            })
+        });
+        if !affected {
+            continue;
+        }
+        match run_rule(app_handle, &state, rule, compiled, false, logging, claims.as_mut()) {
+            Ok(run) => {
+                if let (Some(log_file), Some(log)) = (log_file.as_mut(), run.log) {
+                    if config.log_format == LogFormat::Text {
+                        writeln!(log_file, "{}", tr!(Msg::AutoOrganizeHeader, Local::now().format("%Y-%m-%d %H:%M:%S"))).ok();
+                    }
+                    log_file.write_all(&log).ok();
+                }
+                if !run.result.moved.is_empty() || !run.result.errors.is_empty() {
+                    app_handle.emit("auto-organized", run.result).ok();
+                }
+            }
+            Err(e) => {
+                if let Some(ref mut log_file) = log_file {
+                    write_log_error(log_file, config.log_format, &e);
+                }
+            }
+        }
+    }
+}
+
+/// ファイル (またはフォルダ) を移動する。まず rename を試み、ボリュームをまたぐ場合のみ copy+delete にフォールバックする。
+/// 使用中のファイルの移動に失敗した場合は、移動先にコピーを残さない。
+fn move_file(source: &Path, dest: &Path, options: MoveOptions) -> Result<MoveOutcome, MoveError> {
+    let mut outcome = MoveOutcome {
+        strategy: "rename",
+        warnings: Vec::new(),
+    };
+    let is_dir = source.is_dir();
+    if is_dir && is_same_or_inside(source, dest) {
+        return Err(MoveError::new(tr!(Msg::MoveIntoItself, source.display(), dest.display())));
+    }
+    match fs::rename(source, dest) {
+        Ok(_) => Ok(outcome),
+        Err(e) if is_cross_device_error(&e) => {
+            outcome.strategy = "copy+delete";
+            let dest_existed = dest.exists();
+            copy_into(source, dest, is_dir, dest_existed, &options, Msg::MoveFailed, &mut outcome)?;
+            if options.use_trash {
+                match trash::delete(source) {
+                    Ok(_) => {
+                        outcome.strategy = "copy+trash";
+                        return Ok(outcome);
+                    }
+                    Err(e) => outcome.warnings.push(tr!(Msg::TrashFailed, source.display(), e)),
+                }
+            }
+            let removed = if is_dir { fs::remove_dir_all(source) } else { fs::remove_file(source) };
+            if let Err(e) = removed {
+                let error = MoveError::io(tr!(Msg::DeleteAfterCopyFailed, source.display(), e), &e);
+                // 元ファイルが使用中で消せなかった場合は、移動しなかったものとしてコピーを取り消す。
+                // フォルダは一部が削除済みの可能性があるため、コピーを残す
+                if error.in_use && !is_dir && !dest_existed {
+                    fs::remove_file(dest).ok();
+                }
+                return Err(error);
+            }
+            Ok(outcome)
+        }
+        Err(e) => Err(MoveError::io(tr!(Msg::MoveFailed, source.display(), e), &e)),
+    }
+}
+
+/// ファイル (またはフォルダ) をコピーする。元ファイルは削除しない
+fn copy_file(source: &Path, dest: &Path, options: MoveOptions) -> Result<MoveOutcome, MoveError> {
+    let mut outcome = MoveOutcome {
+        strategy: "copy",
+        warnings: Vec::new(),
+    };
+    let is_dir = source.is_dir();
+    if is_dir && is_same_or_inside(source, dest) {
+        return Err(MoveError::new(tr!(Msg::MoveIntoItself, source.display(), dest.display())));
+    }
+    copy_into(source, dest, is_dir, dest.exists(), &options, Msg::CopyFailed, &mut outcome)?;
+    Ok(outcome)
+}
+
+/// 移動・コピー・リンクのいずれかを行う関数
+type TransferFn = fn(&Path, &Path, MoveOptions) -> Result<MoveOutcome, MoveError>;
+
+/// 使用中で失敗した場合は `retry_delay` おきに `retry_count` 回まで再試行して転送し、結果と再試行した回数を返す
+fn transfer_with_retry(
+    transfer: TransferFn,
+    source: &Path,
+    dest: &Path,
+    options: MoveOptions,
+    retry_count: u32,
+    retry_delay: Duration,
+) -> (Result<MoveOutcome, MoveError>, u32) {
+    let mut attempts = 0;
+    loop {
+        match transfer(source, dest, options.clone()) {
+            Err(e) if e.in_use && attempts < retry_count => {
+                attempts += 1;
+                std::thread::sleep(retry_delay);
+            }
+            moved => return (moved, attempts),
+        }
+    }
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-struct Config {
-    rules: Vec<OrganizeRule>,
+/// 実行した、または実行中の転送
+enum TransferJob {
+    Done((Result<MoveOutcome, MoveError>, u32)),
+    Running(std::thread::JoinHandle<(Result<MoveOutcome, MoveError>, u32)>),
 }
 
-#[derive(Debug, Serialize)]
-struct LoadResult {
-    path: String,
-    config: Config,
+impl TransferJob {
+    fn is_finished(&self) -> bool {
+        match self {
+            TransferJob::Done(_) => true,
+            TransferJob::Running(handle) => handle.is_finished(),
+        }
+    }
+
+    /// 転送が終わるのを待ち、結果と再試行した回数を返す
+    fn wait(self) -> (Result<MoveOutcome, MoveError>, u32) {
+        match self {
+            TransferJob::Done(done) => done,
+            TransferJob::Running(handle) => handle.join()
+                .unwrap_or_else(|_| (Err(MoveError::new(tr!(Msg::UnexpectedRuleError).to_string())), 0)),
+        }
+    }
 }
 
-#[tauri::command]
-async fn load_config(config_path: String) -> Result<Config, String> {
-    let content = fs::read_to_string(&config_path)
-        .map_err(|e| format!("設定ファイルの読み込みに失敗しました: {}", e))?;
-    
-    let config: Config = serde_yaml::from_str(&content)
-        .map_err(|e| format!("YAML解析に失敗しました: {}", e))?;
-    
-    Ok(config)
+/// 結果を記録する前の転送
+struct PendingTransfer {
+    source_file: PathBuf,
+    dest_file: PathBuf,
+    /// compress の場合の元ファイルのサイズ
+    original_size: Option<u64>,
+    /// 上限に数えたサイズ (転送できなければ戻す)
+    reserved: u64,
+    job: TransferJob,
 }
 
-#[tauri::command]
-async fn organize_files(config_path: String) -> Result<Vec<String>, String> {
-    let config = load_config(config_path).await?;
-    let mut results = Vec::new();
-    // ログファイルのパスを決定
-    #[cfg(debug_assertions)]
-    let mut log_file = Some(std::fs::OpenOptions::new()
-        .create(true)
-        .write(true)
-        .truncate(true)
-        .open("C:/python/file-organizer-2025-06-15/file-organizer.log")
-        .map_err(|e| format!("ログファイルの作成/オープンに失敗しました: {}", e))?);
-    #[cfg(not(debug_assertions))]
-    let mut log_file: Option<std::fs::File> = None;
-    
-    for rule in config.rules {
-        let source_path = Path::new(&rule.source_folder);
-        let dest_path = Path::new(&rule.destination_folder);
-        if let Some(ref mut log_file) = log_file {
-            writeln!(log_file, "--- ルール: {} (パターン: {}) ---", rule.name, rule.pattern).ok();
+/// max_concurrency の転送数を守るためのセマフォ
+struct Semaphore {
+    available: Mutex<usize>,
+    released: std::sync::Condvar,
+}
+
+impl Semaphore {
+    fn new(permits: usize) -> std::sync::Arc<Self> {
+        std::sync::Arc::new(Semaphore { available: Mutex::new(permits), released: std::sync::Condvar::new() })
+    }
+
+    /// 空きができるまで待って 1 つ確保する。返した値を破棄すると解放する
+    fn acquire(self: &std::sync::Arc<Self>) -> SemaphorePermit {
+        let mut available = self.available.lock().unwrap();
+        while *available == 0 {
+            available = self.released.wait(available).unwrap();
         }
-        if !source_path.exists() {
-            results.push(format!("警告: ソースフォルダが存在しません: {}", rule.source_folder));
-            if let Some(ref mut log_file) = log_file {
-                writeln!(log_file, "警告: ソースフォルダが存在しません: {}", rule.source_folder).ok();
+        *available -= 1;
+        SemaphorePermit(self.clone())
+    }
+}
+
+struct SemaphorePermit(std::sync::Arc<Semaphore>);
+
+impl Drop for SemaphorePermit {
+    fn drop(&mut self) {
+        *self.0.available.lock().unwrap() += 1;
+        self.0.released.notify_one();
+    }
+}
+
+/// `source` を gzip で圧縮して `dest` に書き込み、閉じた後に元ファイルを削除する
+fn gzip_move_file(source: &Path, dest: &Path, options: MoveOptions) -> Result<MoveOutcome, MoveError> {
+    let use_trash = options.use_trash;
+    let mut outcome = gzip_copy_file(source, dest, options)?;
+    if use_trash {
+        match trash::delete(source) {
+            Ok(_) => {
+                outcome.strategy = "gzip+trash";
+                return Ok(outcome);
             }
-            continue;
+            Err(e) => outcome.warnings.push(tr!(Msg::TrashFailed, source.display(), e)),
         }
-        // 宛先フォルダを作成
-        if !dest_path.exists() {
-            fs::create_dir_all(dest_path)
-                .map_err(|e| format!("宛先フォルダの作成に失敗しました: {}", e))?;
+    }
+    if let Err(e) = fs::remove_file(source) {
+        let error = MoveError::io(tr!(Msg::DeleteAfterArchiveFailed, source.display(), e), &e);
+        // 使用中で消せなかった場合は、移動しなかったものとして圧縮ファイルを取り消す
+        if error.in_use {
+            fs::remove_file(dest).ok();
         }
-        let regex = Regex::new(&rule.pattern)
-            .map_err(|e| format!("正規表現が無効です ({}): {}", rule.pattern, e))?;
-        let entries = fs::read_dir(source_path)
-            .map_err(|e| format!("フォルダの読み取りに失敗しました: {}", e))?;
-        let mut moved_count = 0;
-        for entry in entries {
-            let entry = entry.map_err(|e| format!("ファイルエントリの読み取りに失敗しました: {}", e))?;
-            let file_name = entry.file_name();
-            let file_name_str = file_name.to_string_lossy();
-            let is_file = entry.file_type().map_err(|e| format!("ファイルタイプの取得に失敗しました: {}", e))?.is_file();
-            if is_file {
-                let matched = regex.is_match(&file_name_str);
-                if let Some(ref mut log_file) = log_file {
-                    writeln!(log_file, "ファイル: {} → マッチ: {}", file_name_str, matched).ok();
-                }
-                if matched {
-                    let source_file = entry.path();
-                    let dest_file = dest_path.join(&file_name);
-                    match fs::copy(&source_file, &dest_file) {
-                        Ok(_) => {
-                            match fs::remove_file(&source_file) {
-                                Ok(_) => {
-                                    moved_count += 1;
-                                    results.push(format!("コピー+削除で移動: {} -> {}", source_file.display(), dest_file.display()));
-                                    if let Some(ref mut log_file) = log_file {
-                                        writeln!(log_file, "コピー+削除で移動: {} -> {}", source_file.display(), dest_file.display()).ok();
-                                    }
-                                }
-                                Err(e2) => {
-                                    results.push(format!("コピー後の削除失敗 {}: {}", source_file.display(), e2));
-                                    if let Some(ref mut log_file) = log_file {
-                                        writeln!(log_file, "コピー後の削除失敗 {}: {}", source_file.display(), e2).ok();
-                                    }
-                                }
-                            }
-                        }
-                        Err(e2) => {
-                            results.push(format!("移動失敗 {}: {}", source_file.display(), e2));
-                            if let Some(ref mut log_file) = log_file {
-                                writeln!(log_file, "copy失敗 {}: {}", source_file.display(), e2).ok();
-                            }
-                        }
-                    }
+        return Err(error);
+    }
+    Ok(outcome)
+}
+
+/// `source` を gzip で圧縮して `dest` に書き込む。元ファイルは削除しない。
+/// 失敗した場合は書きかけの圧縮ファイルを削除する
+fn gzip_copy_file(source: &Path, dest: &Path, _options: MoveOptions) -> Result<MoveOutcome, MoveError> {
+    if source.is_dir() {
+        return Err(MoveError::new(tr!(Msg::CompressFolder, source.display())));
+    }
+    let mut outcome = MoveOutcome {
+        strategy: "gzip",
+        warnings: Vec::new(),
+    };
+    let compressed = fs::File::open(source).and_then(|mut input| {
+        let mut encoder = flate2::write::GzEncoder::new(fs::File::create(dest)?, flate2::Compression::default());
+        io::copy(&mut input, &mut encoder)?;
+        // 閉じる前にディスクへ書き出し、元ファイルを消してよい状態にする
+        encoder.finish()?.sync_all()
+    });
+    if let Err(e) = compressed {
+        let message = cleanup_partial_copy(dest, false, tr!(Msg::CompressFailed, source.display(), e));
+        return Err(MoveError::io(message, &e));
+    }
+    if let Err(e) = copy_file_times(source, dest) {
+        outcome.warnings.push(tr!(Msg::RestoreTimesFailed, dest.display(), e));
+    }
+    Ok(outcome)
+}
+
+/// `entries` (元ファイルとアーカイブ内の名前) を 1 つの zip に書き込み、閉じた後のアーカイブのサイズを返す。
+/// 失敗した場合は書きかけのアーカイブを削除する
+fn write_zip_archive(archive: &Path, entries: &[(&Path, String)]) -> Result<u64, String> {
+    fn write(archive: &Path, entries: &[(&Path, String)]) -> zip::result::ZipResult<u64> {
+        let mut writer = zip::ZipWriter::new(fs::File::create(archive)?);
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated);
+        for (source, name) in entries {
+            writer.start_file(name.as_str(), options)?;
+            io::copy(&mut fs::File::open(source)?, &mut writer)?;
+        }
+        let file = writer.finish()?;
+        file.sync_all()?;
+        Ok(file.metadata()?.len())
+    }
+    write(archive, entries).map_err(|e| cleanup_partial_copy(archive, false, tr!(Msg::ArchiveFailed, archive.display(), e)))
+}
+
+/// zip 内のパス。宛先フォルダからの相対パスを `/` 区切りにする
+fn zip_entry_name(dest_root: &Path, dest_file: &Path) -> String {
+    dest_file.strip_prefix(dest_root)
+        .unwrap_or(dest_file)
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// ログに表示する圧縮率 (圧縮後のサイズが元の何 % か)
+fn compression_percent(archive: &CompressedArchive) -> String {
+    format!("{:.1}", archive.ratio * 100.0)
+}
+
+/// `dest` に `source` を指すシンボリックリンクを作る。元ファイルはそのまま残す。
+/// 上書きする場合は既存のファイル (リンク) を置き換える。フォルダは置き換えない
+fn symlink_file(source: &Path, dest: &Path, _options: MoveOptions) -> Result<MoveOutcome, MoveError> {
+    let failed = |e: &io::Error| MoveError::new(tr!(Msg::SymlinkFailed, dest.display(), source.display(), e));
+    // リンクはリンク自身の場所から解決されるため、元ファイルを絶対パスで指す
+    let target = fs::canonicalize(source).map_err(|e| failed(&e))?;
+    if let Ok(existing) = fs::symlink_metadata(dest) {
+        if existing.is_dir() {
+            return Err(MoveError::new(tr!(Msg::SymlinkFailed, dest.display(), source.display(), tr!(Msg::DestinationIsFolder))));
+        }
+        fs::remove_file(dest).map_err(|e| failed(&e))?;
+    }
+    #[cfg(unix)]
+    let linked = std::os::unix::fs::symlink(&target, dest);
+    #[cfg(windows)]
+    let linked = if target.is_dir() {
+        std::os::windows::fs::symlink_dir(&target, dest)
+    } else {
+        std::os::windows::fs::symlink_file(&target, dest)
+    };
+    match linked {
+        Ok(()) => Ok(MoveOutcome { strategy: "symlink", warnings: Vec::new() }),
+        // Windows の ERROR_PRIVILEGE_NOT_HELD (1314): 管理者権限か開発者モードが必要
+        Err(e) if cfg!(windows) && e.raw_os_error() == Some(1314) => {
+            Err(MoveError::new(tr!(Msg::SymlinkPrivilege, dest.display())))
+        }
+        Err(e) => Err(failed(&e)),
+    }
+}
+
+/// `dest` に `source` のハードリンクを作る。元ファイルはそのまま残す。
+/// 上書きする場合は既存のファイルを置き換える。フォルダとボリュームをまたぐリンクは作れない
+fn hardlink_file(source: &Path, dest: &Path, _options: MoveOptions) -> Result<MoveOutcome, MoveError> {
+    let failed = |e: &dyn std::fmt::Display| MoveError::new(tr!(Msg::HardlinkFailed, dest.display(), source.display(), e));
+    if source.is_dir() {
+        return Err(failed(&tr!(Msg::HardlinkFolder)));
+    }
+    if !is_same_volume(source, dest) {
+        return Err(MoveError::new(tr!(Msg::HardlinkCrossVolume, source.display(), dest.display())));
+    }
+    if let Ok(existing) = fs::symlink_metadata(dest) {
+        if existing.is_dir() {
+            return Err(failed(&tr!(Msg::DestinationIsFolder)));
+        }
+        fs::remove_file(dest).map_err(|e| failed(&e))?;
+    }
+    match fs::hard_link(source, dest) {
+        Ok(()) => Ok(MoveOutcome { strategy: "hardlink", warnings: Vec::new() }),
+        Err(e) if is_cross_device_error(&e) => {
+            Err(MoveError::new(tr!(Msg::HardlinkCrossVolume, source.display(), dest.display())))
+        }
+        Err(e) => Err(failed(&e)),
+    }
+}
+
+/// move_file / copy_file の共通のコピー処理。verify が有効ならハッシュで内容を検証し、日時を引き継ぐ。
+/// 失敗した場合、新しく作ったコピーは削除する (上書きの場合は既存ファイルを消さないよう残す)。
+fn copy_into(
+    source: &Path,
+    dest: &Path,
+    is_dir: bool,
+    dest_existed: bool,
+    options: &MoveOptions,
+    failed: Msg,
+    outcome: &mut MoveOutcome,
+) -> Result<(), MoveError> {
+    if is_dir {
+        if let Err(e) = copy_dir_recursive(source, dest) {
+            // 途中までコピーされたフォルダは削除し、元フォルダは残す
+            let mut message = tr!(failed, source.display(), e);
+            if !dest_existed {
+                message = cleanup_partial_copy(dest, true, message);
+            }
+            return Err(MoveError::io(message, &e));
+        }
+        return Ok(());
+    }
+    // 大きなファイルは分割してコピーし、止まって見えないよう進捗を送る
+    let copied = match &options.progress {
+        Some(progress) if fs::metadata(source).is_ok_and(|metadata| metadata.len() > LARGE_FILE_THRESHOLD) => {
+            copy_with_progress(source, dest, progress)
+        }
+        _ => fs::copy(source, dest).map(|_| ()),
+    };
+    if let Err(e) = copied {
+        // 容量不足や切断で途中まで書かれたファイルを、整理済みのように見えるまま残さない
+        let mut message = tr!(failed, source.display(), e);
+        if !dest_existed {
+            message = cleanup_partial_copy(dest, false, message);
+        }
+        return Err(MoveError::io(message, &e));
+    }
+    if options.verify {
+        let error = match (file_hash(source), file_hash(dest)) {
+            (Ok(source_hash), Ok(dest_hash)) if source_hash == dest_hash => None,
+            (Ok(_), Ok(_)) => Some(MoveError::new(tr!(Msg::CopyMismatch, source.display()))),
+            (Err(e), _) | (_, Err(e)) => Some(MoveError::io(tr!(Msg::VerifyFailed, source.display(), e), &e)),
+        };
+        if let Some(mut error) = error {
+            // 不完全なコピーは削除し、元ファイルは残す
+            error.message = cleanup_partial_copy(dest, false, error.message);
+            return Err(error);
+        }
+    }
+    // rename と違い copy では日時が引き継がれないため、元ファイルの日時を復元する
+    if let Err(e) = copy_file_times(source, dest) {
+        outcome.warnings.push(tr!(Msg::RestoreTimesFailed, dest.display(), e));
+    }
+    Ok(())
+}
+
+/// fs::copy と同じくファイルの内容と権限をコピーする。COPY_CHUNK_SIZE ずつ読み書きし、
+/// COPY_PROGRESS_INTERVAL ごとと開始・終了時に進捗を送る
+fn copy_with_progress(source: &Path, dest: &Path, progress: &CopyProgress) -> io::Result<()> {
+    let mut input = fs::File::open(source)?;
+    let metadata = input.metadata()?;
+    let total = metadata.len();
+    let mut output = fs::File::create(dest)?;
+    let mut buffer = vec![0; COPY_CHUNK_SIZE];
+    let (mut copied, mut reported) = (0, 0);
+    progress.emit(source, 0, total);
+    loop {
+        let read = match input.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        output.write_all(&buffer[..read])?;
+        copied += read as u64;
+        if copied - reported >= COPY_PROGRESS_INTERVAL {
+            progress.emit(source, copied, total);
+            reported = copied;
+        }
+    }
+    fs::set_permissions(dest, metadata.permissions())?;
+    progress.emit(source, copied, total);
+    Ok(())
+}
+
+/// コピー (圧縮) に失敗して `dest` に残った書きかけのファイル (フォルダ) を削除し、
+/// 削除できたかどうかを `message` に付け加える。何も作られていなければ `message` をそのまま返す
+fn cleanup_partial_copy(dest: &Path, is_dir: bool, message: String) -> String {
+    let removed = if is_dir { fs::remove_dir_all(dest) } else { fs::remove_file(dest) };
+    match removed {
+        Ok(()) => format!("{} ({})", message, tr!(Msg::PartialCopyRemoved, dest.display())),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => message,
+        Err(e) => format!("{} ({})", message, tr!(Msg::PartialCopyRemoveFailed, dest.display(), e)),
+    }
+}
+
+/// フォルダを中身ごとコピーする。ファイルの日時は元のものを引き継ぐ
+fn copy_dir_recursive(source: &Path, dest: &Path) -> io::Result<()> {
+    fs::create_dir_all(dest)?;
+    for entry in fs::read_dir(source)? {
+        let entry = entry?;
+        let target = dest.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir_recursive(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), &target)?;
+            copy_file_times(&entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+/// ファイルのサイズ。フォルダなら中身の合計
+fn path_size(path: &Path) -> u64 {
+    let Ok(metadata) = fs::metadata(path) else {
+        return 0;
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+    fs::read_dir(path)
+        .map(|entries| entries.flatten().map(|entry| path_size(&entry.path())).sum())
+        .unwrap_or(0)
+}
+
+/// `a` と `b` (まだ存在しなければ存在する祖先) が同じボリューム上にあるか。同じなら rename で移動できる
+fn is_same_volume(a: &Path, b: &Path) -> bool {
+    let existing = |path: &Path| path.ancestors().find(|p| p.exists()).map(Path::to_path_buf);
+    let (Some(a), Some(b)) = (existing(a), existing(b)) else {
+        return false;
+    };
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        matches!((fs::metadata(&a), fs::metadata(&b)), (Ok(a), Ok(b)) if a.dev() == b.dev())
+    }
+    // Windows ではドライブ (または UNC 共有) が同じかで判定する
+    #[cfg(not(unix))]
+    {
+        let prefix = |path: &Path| fs::canonicalize(path).ok()
+            .and_then(|path| path.components().next().map(|c| c.as_os_str().to_string_lossy().to_lowercase()));
+        prefix(&a).is_some() && prefix(&a) == prefix(&b)
+    }
+}
+
+/// `\\server\share\...` (または `//server/share/...`) のネットワークパスなら、共有のルートを返す
+fn network_share_root(path: &str) -> Option<PathBuf> {
+    let (separator, rest) = match path.strip_prefix(r"\\") {
+        Some(rest) => ('\\', rest),
+        None => ('/', path.strip_prefix("//")?),
+    };
+    let mut parts = rest.split(['\\', '/']).filter(|part| !part.is_empty());
+    let (server, share) = (parts.next()?, parts.next()?);
+    // `\\?\C:\` などのデバイスパスはローカル
+    if server == "?" || server == "." {
+        return None;
+    }
+    Some(PathBuf::from(format!("{0}{0}{1}{0}{2}{0}", separator, server, share)))
+}
+
+/// `path` に `timeout` 以内にアクセスできるか確かめる。応答の無い共有で処理が止まらないよう別スレッドで stat する
+fn check_reachable(path: &Path, timeout: Duration) -> Result<(), String> {
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let target = path.to_path_buf();
+    std::thread::spawn(move || {
+        sender.send(fs::metadata(&target).map(|_| ())).ok();
+    });
+    match receiver.recv_timeout(timeout) {
+        Ok(Ok(())) => Ok(()),
+        Ok(Err(e)) => Err(e.to_string()),
+        Err(_) => Err(tr!(Msg::NetworkTimeout, timeout.as_millis())),
+    }
+}
+
+/// `target` が `dir` 自身またはその配下を指すかどうか。まだ存在しない `target` は存在する祖先で正規化して判定する
+fn is_same_or_inside(dir: &Path, target: &Path) -> bool {
+    let dir = fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf());
+    let target = target.ancestors()
+        .find_map(|ancestor| {
+            let canonical = fs::canonicalize(ancestor).ok()?;
+            Some(canonical.join(target.strip_prefix(ancestor).ok()?))
+        })
+        .unwrap_or_else(|| target.to_path_buf());
+    target.starts_with(&dir)
+}
+
+/// remove_empty_dirs の削除処理
+struct EmptyDirCleanup<'a> {
+    /// 削除しない宛先フォルダ (ソースフォルダの中にある場合)
+    keep: &'a Path,
+    ignored: Vec<glob::Pattern>,
+    /// 隠しファイル・システムファイルしか無いフォルダも削除する
+    with_hidden: bool,
+    removed: Vec<PathBuf>,
+    warnings: Vec<String>,
+}
+
+impl EmptyDirCleanup<'_> {
+    /// `dir` の下のサブフォルダを深いほうから調べて、空のものを削除する。`dir` 自身は削除せず、空になったかを返す
+    fn visit(&mut self, dir: &Path) -> bool {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                self.warnings.push(tr!(Msg::EmptyDirScanFailed, dir.display(), e));
+                return false;
+            }
+        };
+        let mut empty = true;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            // シンボリックリンクはたどらず、中身のあるものとして扱う
+            let is_dir = entry.file_type().is_ok_and(|file_type| file_type.is_dir());
+            if !is_dir {
+                if !(self.with_hidden && is_hidden_or_system(&entry)) {
+                    empty = false;
+                }
+                continue;
+            }
+            // 除外したフォルダと宛先フォルダの中は整理の対象外なので、たどらずに残す
+            if self.ignored.iter().any(|pattern| pattern.matches(&name)) || is_same_or_inside(self.keep, &path) {
+                empty = false;
+                continue;
+            }
+            // 宛先フォルダを含むフォルダは、中の空フォルダだけを削除する
+            if !self.visit(&path) || is_same_or_inside(&path, self.keep) {
+                empty = false;
+                continue;
+            }
+            let removed = if self.with_hidden { fs::remove_dir_all(&path) } else { fs::remove_dir(&path) };
+            match removed {
+                Ok(()) => self.removed.push(path),
+                Err(e) => {
+                    self.warnings.push(tr!(Msg::EmptyDirRemoveFailed, path.display(), e));
+                    empty = false;
                 }
             }
         }
-        results.push(format!("ルール '{}': {}個のファイルを移動しました", rule.name, moved_count));
-        if let Some(ref mut log_file) = log_file {
-            writeln!(log_file, "ルール '{}': {}個のファイルを移動しました", rule.name, moved_count).ok();
+        empty
+    }
+}
+
+/// 名前が `.` で始まるか、Windows で隠し属性の付いたエントリか
+fn is_hidden(entry: &fs::DirEntry) -> bool {
+    if entry.file_name().to_string_lossy().starts_with('.') {
+        return true;
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::MetadataExt;
+        // FILE_ATTRIBUTE_HIDDEN
+        if entry.metadata().is_ok_and(|metadata| metadata.file_attributes() & 0x2 != 0) {
+            return true;
         }
     }
-    Ok(results)
+    false
+}
+
+/// 隠しファイル (`.` で始まるもの、Windows では隠し・システム属性のもの) か、OS が自動で作るファイルか
+fn is_hidden_or_system(entry: &fs::DirEntry) -> bool {
+    let name = entry.file_name();
+    let name = name.to_string_lossy();
+    if name.starts_with('.') || ["Thumbs.db", "desktop.ini"].iter().any(|system| name.eq_ignore_ascii_case(system)) {
+        return true;
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::MetadataExt;
+        // FILE_ATTRIBUTE_HIDDEN | FILE_ATTRIBUTE_SYSTEM
+        if entry.metadata().is_ok_and(|metadata| metadata.file_attributes() & 0x6 != 0) {
+            return true;
+        }
+    }
+    false
+}
+
+/// 同じ実行で宛先が重なるかを比べるためのキー。
+/// Windows のファイルシステムは大文字と小文字を区別しないため、`Report.pdf` と `report.pdf` を同じ宛先として扱う
+fn destination_key(path: &Path) -> PathBuf {
+    if cfg!(windows) {
+        PathBuf::from(path.to_string_lossy().to_lowercase())
+    } else {
+        path.to_path_buf()
+    }
+}
+
+/// `report.pdf` が存在する場合に `report (1).pdf`, `report (2).pdf` ... のうち未使用の最初のパスを返す。
+/// `taken` に含まれるパス (destination_key で正規化したもの) も使用中として扱う
+fn next_available_path(path: &Path, taken: &std::collections::HashSet<PathBuf>) -> PathBuf {
+    let parent = path.parent().unwrap_or_else(|| Path::new(""));
+    let stem = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    let extension = path.extension().map(|e| e.to_string_lossy().into_owned());
+    let mut counter = 1;
+    loop {
+        let candidate_name = match &extension {
+            Some(ext) => format!("{} ({}).{}", stem, counter, ext),
+            None => format!("{} ({})", stem, counter),
+        };
+        let candidate = parent.join(candidate_name);
+        if !candidate.exists() && !taken.contains(&destination_key(&candidate)) {
+            return candidate;
+        }
+        counter += 1;
+    }
+}
+
+/// `dest` と、rename で付けられる `name (1).ext` などの既存ファイルのうち、`source` と内容が同じものを探す
+fn find_duplicate(source: &Path, dest: &Path) -> io::Result<Option<PathBuf>> {
+    let size = fs::metadata(source)?.len();
+    let mut source_hash = None;
+    let parent = dest.parent().unwrap_or_else(|| Path::new(""));
+    let stem = dest.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    let extension = dest.extension().map(|e| e.to_string_lossy().into_owned());
+    let mut candidate = dest.to_path_buf();
+    let mut counter = 1;
+    while candidate.exists() {
+        // サイズが違えばハッシュを計算するまでもない
+        if candidate.is_file() && fs::metadata(&candidate)?.len() == size {
+            let hash = match source_hash {
+                Some(hash) => hash,
+                None => *source_hash.insert(file_hash(source)?),
+            };
+            if file_hash(&candidate)? == hash {
+                return Ok(Some(candidate));
+            }
+        }
+        let candidate_name = match &extension {
+            Some(ext) => format!("{} ({}).{}", stem, counter, ext),
+            None => format!("{} ({})", stem, counter),
+        };
+        candidate = parent.join(candidate_name);
+        counter += 1;
+    }
+    Ok(None)
+}
+
+fn file_hash(path: &Path) -> io::Result<blake3::Hash> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = blake3::Hasher::new();
+    io::copy(&mut file, &mut hasher)?;
+    Ok(hasher.finalize())
+}
+
+fn copy_file_times(source: &Path, dest: &Path) -> io::Result<()> {
+    let metadata = fs::metadata(source)?;
+    let accessed = FileTime::from_last_access_time(&metadata);
+    let modified = FileTime::from_last_modification_time(&metadata);
+    filetime::set_file_times(dest, accessed, modified)
+}
+
+fn is_cross_device_error(e: &io::Error) -> bool {
+    e.kind() == io::ErrorKind::CrossesDevices
+}
+
+/// 他のプログラムがファイルを開いている (共有違反・ロック違反) ことによるエラーかどうか
+fn is_file_in_use_error(e: &io::Error) -> bool {
+    // Windows の ERROR_SHARING_VIOLATION (32) と ERROR_LOCK_VIOLATION (33) だけを見る。
+    // 単なる権限エラーは使用中ではないので、再試行せずにそのまま失敗させる
+    cfg!(windows) && matches!(e.raw_os_error(), Some(32) | Some(33))
+}
+
+/// バックアップファイル名 `<元のファイル名>.backup(<日時>)` に使う日時の既定の形式
+const BACKUP_TIMESTAMP_FORMAT: &str = "%Y年%m月%d日%H時%M分%S秒";
+
+/// バックアップファイル名に使う日時を `format` (未指定なら既定の形式) で作る。
+/// ファイル名に使えない文字を含む形式と、rotate_backups で日時として読み戻せない形式はエラー
+fn backup_timestamp(format: Option<&str>, now: NaiveDateTime) -> Result<String, String> {
+    use std::fmt::Write as _;
+    let format = format.unwrap_or(BACKUP_TIMESTAMP_FORMAT);
+    let invalid = || tr!(Msg::InvalidBackupTimestampFormat, format);
+    let mut timestamp = String::new();
+    write!(timestamp, "{}", now.format(format)).map_err(|_| invalid())?;
+    let unsafe_name = timestamp.trim().is_empty()
+        || timestamp.chars().any(|c| c.is_control() || r#"<>:"/\|?*"#.contains(c));
+    if unsafe_name || NaiveDateTime::parse_from_str(&timestamp, format).is_err() {
+        return Err(invalid());
+    }
+    Ok(timestamp)
+}
+
+/// `dir` 内にある `original_filename` のバックアップのうち、新しいものから `keep` 件を残して削除する。
+/// 日時は `format` と既定の形式で読み取り、どちらにも一致しないファイルには触れない。削除したパスを返す。
+fn rotate_backups(dir: &Path, original_filename: &str, keep: usize, format: Option<&str>) -> Result<Vec<PathBuf>, String> {
+    let prefix = format!("{}.backup(", original_filename);
+    let mut backups = Vec::new();
+    let entries = fs::read_dir(dir)
+        .map_err(|e| tr!(Msg::BackupFolderReadFailed, e))?;
+    for entry in entries {
+        let entry = entry.map_err(|e| tr!(Msg::EntryReadFailed, e))?;
+        if !entry.file_type().is_ok_and(|file_type| file_type.is_file()) {
+            continue;
+        }
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let Some(timestamp) = name.strip_prefix(&prefix).and_then(|rest| rest.strip_suffix(')')) else {
+            continue;
+        };
+        let parsed = format.into_iter()
+            .chain([BACKUP_TIMESTAMP_FORMAT])
+            .find_map(|format| NaiveDateTime::parse_from_str(timestamp, format).ok());
+        let Some(created_at) = parsed else {
+            continue;
+        };
+        backups.push((created_at, entry.path()));
+    }
+    backups.sort_by_key(|(created_at, _)| std::cmp::Reverse(*created_at));
+
+    let mut removed = Vec::new();
+    // 作成したばかりのバックアップは必ず残す
+    for (_, path) in backups.into_iter().skip(keep.max(1)) {
+        fs::remove_file(&path)
+            .map_err(|e| tr!(Msg::OldBackupRemoveFailed, path.display(), e))?;
+        removed.push(path);
+    }
+    Ok(removed)
 }
 
 async fn backup_rules_handler(app_handle: &AppHandle) -> Result<String, String> {
     let last_config_path_str = get_last_config_path()?
-        .ok_or("最後に使用した設定ファイルが見つかりません。まずは一度ルールを読み込んで実行してください。")?;
+        .ok_or(tr!(Msg::NoLastConfig))?;
     let source_path = Path::new(&last_config_path_str);
 
     if !source_path.exists() {
-        return Err(format!("バックアップ元のファイルが見つかりません: {}", last_config_path_str));
+        return Err(tr!(Msg::BackupSourceMissing, last_config_path_str));
     }
+    // 設定が読めない場合でもバックアップ自体は行う
+    let config = read_config(&last_config_path_str).ok();
+    let backup_keep = config.as_ref().and_then(|config| config.backup_keep);
+    let timestamp_format = config.and_then(|config| config.backup_timestamp_format);
     
     let (tx, rx) = oneshot::channel();
     app_handle.dialog().file().pick_folder(move |path| {
@@ -146,9 +5118,8 @@ async fn backup_rules_handler(app_handle: &AppHandle) -> Result<String, String>
 
     if let Some(dest_folder) = dest_folder_option {
         if let Some(dest_folder_path) = dest_folder.as_path() {
-            let now = Local::now();
-            let timestamp = now.format("%Y年%m月%d日%H時%M分%S秒").to_string();
-            
+            let timestamp = backup_timestamp(timestamp_format.as_deref(), Local::now().naive_local())?;
+
             let original_filename = source_path.file_name()
                 .and_then(|name| name.to_str())
                 .unwrap_or("rules.yaml");
@@ -157,22 +5128,174 @@ async fn backup_rules_handler(app_handle: &AppHandle) -> Result<String, String>
             
             let dest_path = dest_folder_path.join(backup_filename);
 
-            fs::copy(&source_path, &dest_path)
-                .map_err(|e| format!("バックアップに失敗しました: {}", e))?;
-            
-            Ok(format!("バックアップが完了しました。\n保存先: {}", dest_path.to_string_lossy()))
+            fs::copy(source_path, &dest_path)
+                .map_err(|e| tr!(Msg::BackupFailed, e))?;
+
+            let mut message = tr!(Msg::BackupDone, dest_path.to_string_lossy());
+            if let Some(keep) = backup_keep {
+                let removed = rotate_backups(dest_folder_path, original_filename, keep, timestamp_format.as_deref())?;
+                if !removed.is_empty() {
+                    message.push_str(&tr!(Msg::OldBackupsRemoved, removed.len()));
+                }
+            }
+            Ok(message)
         } else {
-            Err("無効なフォルダパスが選択されました。".to_string())
+            Err(tr!(Msg::InvalidFolderSelected).to_string())
         }
     } else {
-        Ok("バックアップ処理はキャンセルされました。".to_string())
+        Ok(tr!(Msg::BackupCancelled).to_string())
+    }
+}
+
+/// 選択したバックアップファイルで最後に使用した設定ファイルを上書きする。
+/// 上書き前の設定は同じフォルダに安全のためバックアップしておく。
+async fn restore_rules_handler(app_handle: &AppHandle) -> Result<String, String> {
+    let last_config_path_str = get_last_config_path()?
+        .ok_or(tr!(Msg::NoLastConfig))?;
+    let target_path = Path::new(&last_config_path_str);
+
+    let (tx, rx) = oneshot::channel();
+    app_handle.dialog().file().pick_file(move |file_path| {
+        let _ = tx.send(file_path);
+    });
+
+    let Some(backup_file) = rx.await.map_err(|e| e.to_string())? else {
+        return Ok(tr!(Msg::RestoreCancelled).to_string());
+    };
+    let backup_path = backup_file.as_path()
+        .ok_or(tr!(Msg::InvalidFileSelected))?
+        .to_path_buf();
+    let is_backup_file = backup_path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.contains(".backup(") && name.ends_with(')'));
+    if !is_backup_file {
+        return Err(tr!(Msg::NotBackupFile, backup_path.display()));
+    }
+
+    let (tx, rx) = oneshot::channel();
+    app_handle.dialog()
+        .message(tr!(Msg::RestoreConfirm, backup_path.display(), target_path.display()))
+        .title(tr!(Msg::RestoreTitle))
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancel)
+        .show(move |confirmed| {
+            let _ = tx.send(confirmed);
+        });
+    if !rx.await.map_err(|e| e.to_string())? {
+        return Ok(tr!(Msg::RestoreCancelled).to_string());
+    }
+
+    let mut message = String::new();
+    if target_path.exists() {
+        let original_filename = target_path.file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("rules.yaml");
+        let timestamp_format = read_config(&last_config_path_str)
+            .ok()
+            .and_then(|config| config.backup_timestamp_format);
+        let timestamp = backup_timestamp(timestamp_format.as_deref(), Local::now().naive_local())?;
+        let safety_path = target_path.with_file_name(format!("{}.backup({})", original_filename, timestamp));
+        fs::copy(target_path, &safety_path)
+            .map_err(|e| tr!(Msg::SafetyBackupFailed, e))?;
+        message.push_str(&tr!(Msg::SafetyBackupDone, safety_path.display()));
+    }
+
+    fs::copy(&backup_path, target_path)
+        .map_err(|e| tr!(Msg::RestoreFailed, e))?;
+
+    Ok(tr!(Msg::RestoreDone, message, backup_path.display(), target_path.display()))
+}
+
+/// OS のファイルマネージャーで指定したファイル (フォルダ) を選択した状態で開く
+#[tauri::command]
+async fn reveal_in_explorer(app_handle: AppHandle, path: String) -> Result<(), String> {
+    let target = Path::new(&path);
+    if !target.exists() {
+        return Err(tr!(Msg::PathMissing, path));
+    }
+    let shell = app_handle.shell();
+    #[cfg(target_os = "windows")]
+    let command = shell.command("explorer").arg(format!("/select,{}", path.replace('/', "\\")));
+    #[cfg(target_os = "macos")]
+    let command = shell.command("open").args(["-R", path.as_str()]);
+    // Linux では選択状態にできないため、含まれているフォルダを開く
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let command = {
+        let folder = if target.is_dir() { target } else { target.parent().unwrap_or(target) };
+        shell.command("xdg-open").arg(folder)
+    };
+    command.spawn()
+        .map_err(|e| tr!(Msg::FileManagerFailed, e))?;
+    Ok(())
+}
+
+/// 設定で使われるログファイルのパスを返す。ログが無効ならエラー
+#[tauri::command]
+async fn get_log_path(config_path: String) -> Result<String, String> {
+    let config = read_config(&config_path)?;
+    let _language = i18n::use_language(config.language());
+    if config.logging == Some(false) {
+        return Err(tr!(Msg::LoggingDisabled).to_string());
+    }
+    Ok(log_file_path(&config)?.to_string_lossy().into_owned())
+}
+
+/// ログファイルを既定のテキストエディタで開く
+#[tauri::command]
+async fn open_log(app_handle: AppHandle, config_path: String) -> Result<(), String> {
+    let path = get_log_path(config_path).await?;
+    if !Path::new(&path).is_file() {
+        return Err(tr!(Msg::LogFileMissing, path));
+    }
+    let shell = app_handle.shell();
+    #[cfg(target_os = "windows")]
+    let command = shell.command("explorer").arg(path.replace('/', "\\"));
+    #[cfg(target_os = "macos")]
+    let command = shell.command("open").args(["-t", path.as_str()]);
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let command = shell.command("xdg-open").arg(&path);
+    command.spawn()
+        .map_err(|e| tr!(Msg::LogEditorFailed, e))?;
+    Ok(())
+}
+
+/// 設定ファイルを OS の既定のアプリ (テキストエディタ) で開く
+#[tauri::command]
+async fn edit_config(app_handle: AppHandle, config_path: String) -> Result<(), String> {
+    let path = Path::new(&config_path);
+    if !path.exists() {
+        return Err(tr!(Msg::ConfigFileMissing, config_path));
     }
+    if !path.is_file() {
+        return Err(tr!(Msg::ConfigPathNotFile, config_path));
+    }
+    let shell = app_handle.shell();
+    #[cfg(target_os = "windows")]
+    let command = shell.command("explorer").arg(config_path.replace('/', "\\"));
+    #[cfg(target_os = "macos")]
+    let command = shell.command("open").args(["-t", config_path.as_str()]);
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let command = shell.command("xdg-open").arg(&config_path);
+    command.spawn()
+        .map_err(|e| tr!(Msg::ConfigEditorFailed, e))?;
+    Ok(())
 }
 
-// ファイル・フォルダ選択機能は一旦無効化
 #[tauri::command]
-async fn select_folder() -> Result<String, String> {
-    Err("フォルダ選択機能は現在無効です。手動でパスを入力してください。".to_string())
+async fn select_folder(app_handle: tauri::AppHandle) -> Result<String, String> {
+    let (tx, rx) = oneshot::channel();
+    app_handle.dialog().file().pick_folder(move |path| {
+        let _ = tx.send(path);
+    });
+
+    let folder_option = rx.await.map_err(|e| e.to_string())?;
+
+    match folder_option {
+        Some(folder) => folder.as_path()
+            .map(|p| p.to_string_lossy().into_owned())
+            .ok_or_else(|| tr!(Msg::InvalidFolderSelected).to_string()),
+        None => Err(tr!(Msg::FolderSelectCancelled).to_string())
+    }
 }
 
 #[tauri::command]
@@ -180,7 +5303,7 @@ async fn select_file(app_handle: tauri::AppHandle) -> Result<LoadResult, String>
     let (tx, rx) = oneshot::channel();
     app_handle.dialog()
         .file()
-        .add_filter("YAML", &["yaml", "yml"])
+        .add_filter(tr!(Msg::ConfigFileFilter), &["yaml", "yml", "json", "toml"])
         .pick_file(move |file_path| {
             let _ = tx.send(file_path);
         });
@@ -201,38 +5324,90 @@ async fn select_file(app_handle: tauri::AppHandle) -> Result<LoadResult, String>
                     config,
                 })
             } else {
-                Err("パスの変換に失敗しました".to_string())
+                Err(tr!(Msg::PathConvertFailed).to_string())
             }
         }
-        None => Err("ファイル選択がキャンセルされました".to_string())
+        None => Err(tr!(Msg::FileSelectCancelled).to_string())
     }
 }
 
+/// アプリの設定ディレクトリ (存在するとは限らない)
+fn app_config_dir() -> Result<PathBuf, String> {
+    Ok(config_dir()
+        .ok_or_else(|| tr!(Msg::ConfigDirNotFound).to_string())?
+        .join("file-organizer"))
+}
+
+/// アプリの設定ディレクトリのパスを返す。無ければ作成する
+#[tauri::command]
+async fn get_app_config_dir() -> Result<String, String> {
+    let config_dir = app_config_dir()?;
+    fs::create_dir_all(&config_dir)
+        .map_err(|e| tr!(Msg::ConfigDirCreateFailed, e))?;
+    Ok(config_dir.to_string_lossy().into_owned())
+}
+
 #[tauri::command]
 async fn save_last_config_path(_app_handle: tauri::AppHandle, config_path: String) -> Result<(), String> {
-    let config_dir = config_dir()
-        .ok_or_else(|| "設定ディレクトリの取得に失敗しました".to_string())?
-        .join("file-organizer");
+    let config_dir = app_config_dir()?;
     fs::create_dir_all(&config_dir)
-        .map_err(|e| format!("設定ディレクトリの作成に失敗しました: {}", e))?;
+        .map_err(|e| tr!(Msg::ConfigDirCreateFailed, e))?;
     let config_file = config_dir.join("last_config.txt");
     let mut file = fs::File::create(config_file)
-        .map_err(|e| format!("設定ファイルの作成に失敗しました: {}", e))?;
+        .map_err(|e| tr!(Msg::LastConfigCreateFailed, e))?;
     file.write_all(config_path.as_bytes())
-        .map_err(|e| format!("設定の保存に失敗しました: {}", e))?;
-    Ok(())
+        .map_err(|e| tr!(Msg::LastConfigWriteFailed, e))?;
+
+    let mut recent = read_recent_configs()?;
+    recent.retain(|path| path != &config_path);
+    recent.insert(0, config_path);
+    recent.truncate(MAX_RECENT_CONFIGS);
+    write_recent_configs(&recent)
+}
+
+/// 最近使った設定ファイルとして記憶する件数
+const MAX_RECENT_CONFIGS: usize = 10;
+
+fn read_recent_configs() -> Result<Vec<String>, String> {
+    let recent_file = app_config_dir()?.join("recent_configs.json");
+    if !recent_file.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(recent_file)
+        .map_err(|e| tr!(Msg::RecentConfigsReadFailed, e))?;
+    // 壊れている場合は一覧を作り直す
+    Ok(serde_json::from_str(&content).unwrap_or_default())
+}
+
+fn write_recent_configs(recent: &[String]) -> Result<(), String> {
+    let recent_file = app_config_dir()?.join("recent_configs.json");
+    let content = serde_json::to_string_pretty(recent)
+        .map_err(|e| tr!(Msg::RecentConfigsSerializeFailed, e))?;
+    fs::write(recent_file, content)
+        .map_err(|e| tr!(Msg::RecentConfigsWriteFailed, e))
+}
+
+/// 最近使った設定ファイルを新しい順に返す。存在しなくなったファイルは一覧から取り除く
+#[tauri::command]
+async fn load_recent_configs() -> Result<Vec<String>, String> {
+    let recent = read_recent_configs()?;
+    let existing: Vec<String> = recent.iter()
+        .filter(|path| Path::new(path).exists())
+        .cloned()
+        .collect();
+    if existing.len() != recent.len() {
+        write_recent_configs(&existing)?;
+    }
+    Ok(existing)
 }
 
 fn get_last_config_path() -> Result<Option<String>, String> {
-    let config_dir = config_dir()
-        .ok_or_else(|| "設定ディレクトリの取得に失敗しました".to_string())?
-        .join("file-organizer");
-    let config_file = config_dir.join("last_config.txt");
+    let config_file = app_config_dir()?.join("last_config.txt");
     if !config_file.exists() {
         return Ok(None);
     }
     let content = fs::read_to_string(config_file)
-        .map_err(|e| format!("設定ファイルの読み込みに失敗しました: {}", e))?;
+        .map_err(|e| tr!(Msg::ConfigReadFailed, e))?;
     Ok(Some(content))
 }
 
@@ -241,23 +5416,49 @@ async fn load_last_config_path(_app_handle: tauri::AppHandle) -> Result<Option<S
     get_last_config_path()
 }
 
+/// 最後に開いた設定ファイルと最近使った設定ファイルの記録を消す。記録が無くても成功とする
+#[tauri::command]
+async fn clear_recent_configs() -> Result<(), String> {
+    let config_dir = app_config_dir()?;
+    for file_name in ["last_config.txt", "recent_configs.json"] {
+        match fs::remove_file(config_dir.join(file_name)) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => {
+                return Err(tr!(Msg::RecentConfigsRemoveFailed, e));
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .setup(|app| {
             let handle = app.handle();
+            // メニューは起動時に作るため、前回の設定の language で表示する
+            if let Some(config) = get_last_config_path().ok().flatten().and_then(|path| read_config(&path).ok()) {
+                i18n::set_default_language(config.language());
+            }
             let backup_item =
-                tauri::menu::MenuItem::with_id(handle, "backup_rules", "ルールのバックアップ", true, None::<&str>)?;
-            let menu = tauri::menu::Menu::with_items(handle, &[&backup_item])?;
+                tauri::menu::MenuItem::with_id(handle, "backup_rules", tr!(Msg::MenuBackupRules), true, None::<&str>)?;
+            let restore_item =
+                tauri::menu::MenuItem::with_id(handle, "restore_rules", tr!(Msg::MenuRestoreRules), true, None::<&str>)?;
+            let menu = tauri::menu::Menu::with_items(handle, &[&backup_item, &restore_item])?;
             app.set_menu(menu)?;
             Ok(())
         })
         .on_menu_event(|window, event| {
-            if event.id() == "backup_rules" {
+            if event.id() == "backup_rules" || event.id() == "restore_rules" {
                 let window = window.clone();
+                let is_restore = event.id() == "restore_rules";
                 tauri::async_runtime::spawn(async move {
                     let app_handle = window.app_handle();
-                    let result = backup_rules_handler(app_handle).await;
+                    let result = if is_restore {
+                        restore_rules_handler(app_handle).await
+                    } else {
+                        backup_rules_handler(app_handle).await
+                    };
                     let dialog = app_handle.dialog();
                     match result {
                         Ok(message) => {
@@ -270,15 +5471,50 @@ pub fn run() {
                 });
             }
         })
+        .manage(OrganizeState::default())
+        .manage(WatchState::default())
+        .manage(ScheduleState::default())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_shell::init())
         .invoke_handler(tauri::generate_handler![
             load_config,
+            load_configs,
+            save_config,
+            validate_config,
+            duplicate_rule,
+            rename_rule,
+            suggest_rules,
+            test_pattern,
+            describe_pattern,
             organize_files,
+            organize_single_rule,
+            list_matches,
+            count_source_files,
+            check_destinations,
+            detect_collisions,
+            diff_preview,
+            preview_tree,
+            simulate_config,
+            export_results,
+            confirm_organize,
+            cancel_organize,
+            undo_last_organize,
+            load_history,
+            reveal_in_explorer,
+            get_log_path,
+            open_log,
+            edit_config,
+            start_watching,
+            stop_watching,
+            start_schedule,
+            stop_schedule,
             select_folder,
             select_file,
             save_last_config_path,
-            load_last_config_path
+            load_last_config_path,
+            load_recent_configs,
+            clear_recent_configs,
+            get_app_config_dir
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -1,16 +1,166 @@
 import React, { useState, useEffect } from 'react';
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 import './App.css';
 
 interface OrganizeRule {
   name: string;
-  source_folder: string;
+  enabled?: boolean;
+  source_folder?: string;
+  source_folders?: string[];
   pattern: string;
   destination_folder: string;
+  on_conflict?: 'overwrite' | 'skip' | 'rename' | 'dedupe' | 'keep_newer';
+  delete_older_source?: boolean;
+  mode?: 'move' | 'copy' | 'by_extension' | 'alphabetical' | 'symlink' | 'hardlink';
+  recursive?: boolean;
+  max_depth?: number;
+  flatten?: boolean;
+  preserve_structure_from?: string;
+  rename_template?: string;
+  date_subfolder?: string;
+  case_insensitive?: boolean;
+  match_target?: 'filename' | 'path' | 'fullpath';
+  sanitize?: SanitizeOptions;
+  exclude?: string[];
+  extensions?: string[];
+  content_type?: string[];
+  min_size?: number;
+  max_size?: number;
+  min_age_days?: number;
+  max_age_days?: number;
+  include_directories?: boolean;
+  include_hidden?: boolean;
+  use_trash?: boolean;
+  verify?: boolean;
+  write_sidecar?: boolean;
+  retry_count?: number;
+  retry_delay_ms?: number;
+  create_destination?: boolean;
+  priority?: number;
+  network_timeout_ms?: number;
+  keep_at_least?: number;
+  compress?: 'gzip' | 'zip';
+  max_concurrency?: number;
+  remove_empty_dirs?: boolean;
+  remove_empty_dirs_with_hidden?: boolean;
+}
+
+interface SanitizeOptions {
+  lowercase?: boolean;
+  replace_spaces?: boolean;
+  strip_illegal?: boolean;
 }
 
 interface Config {
   rules: OrganizeRule[];
+  log_file?: string;
+  logging?: boolean;
+  log_format?: 'text' | 'json';
+  max_log_bytes?: number;
+  log_keep?: number;
+  backup_keep?: number;
+  backup_timestamp_format?: string;
+  language?: "ja" | "en";
+  first_match_wins?: boolean;
+  transactional?: boolean;
+  confirm_threshold?: number;
+  auto_confirm?: boolean;
+  history_keep?: number;
+  incremental?: boolean;
+  verbose_log?: boolean;
+  post_run_command?: string;
+  schedule?: string;
+  unmatched_destination?: string;
+  max_files_per_run?: number;
+  max_total_bytes_per_run?: number;
+}
+
+interface ScheduledRun {
+  config_path: string;
+  started_at: string;
+  moved: number;
+  error?: string;
+  next_run?: string;
+}
+
+interface MovedFile {
+  source: string;
+  destination: string;
+  strategy: string;
+  size: number;
+  timestamp: string;
+}
+
+interface CompressedArchive {
+  path: string;
+  files: number;
+  original_size: number;
+  compressed_size: number;
+  ratio: number;
+}
+
+interface OrganizeResult {
+  rule_name: string;
+  dry_run: boolean;
+  moved: MovedFile[];
+  bytes: number;
+  archives: CompressedArchive[];
+  skipped?: SkippedFile[];
+  warnings: string[];
+  errors: string[];
+}
+
+type SkipReason = 'no_match' | 'excluded' | 'filter_size' | 'filter_age' | 'conflict' | 'locked' | 'hidden';
+
+interface SkippedFile {
+  path: string;
+  reason: SkipReason;
+  message?: string;
+}
+
+interface RuleSummary {
+  rule_name: string;
+  moved: number;
+  bytes: number;
+}
+
+interface OrganizeSummary {
+  total_moved: number;
+  total_bytes: number;
+  rules: RuleSummary[];
+  elapsed_ms: number;
+}
+
+interface PostRunResult {
+  command: string;
+  success: boolean;
+  exit_code: number | null;
+  stdout: string;
+  stderr: string;
+  error?: string;
+}
+
+interface OrganizeReport {
+  results: OrganizeResult[];
+  summary: OrganizeSummary;
+  rule_order: string[];
+  post_run?: PostRunResult;
+  estimated_seconds?: number;
+}
+
+interface OrganizeProgress {
+  rule_name: string;
+  processed: number;
+  total: number;
+  current_file: string;
+}
+
+interface FileProgress {
+  rule_name: string;
+  file: string;
+  copied: number;
+  total: number;
 }
 
 interface LoadResult {
@@ -18,12 +168,47 @@ interface LoadResult {
   config: Config;
 }
 
+interface ConfirmRequest {
+  id: number;
+  rule_name: string;
+  count: number;
+}
+
 type StatusType = 'success' | 'error' | 'loading' | null;
 
+const SKIP_REASON_LABELS: Record<SkipReason, string> = {
+  no_match: 'パターンに不一致',
+  excluded: '除外',
+  filter_size: 'サイズの条件',
+  filter_age: '日付の条件',
+  conflict: '宛先に同名のファイル',
+  locked: '使用中',
+  hidden: '隠しファイル',
+};
+
+const formatBytes = (bytes: number): string => {
+  const units = ['B', 'KB', 'MB', 'GB', 'TB'];
+  let value = bytes;
+  let unit = 0;
+  while (value >= 1024 && unit < units.length - 1) {
+    value /= 1024;
+    unit++;
+  }
+  return `${unit === 0 ? value : value.toFixed(1)} ${units[unit]}`;
+};
+
 const App: React.FC = () => {
   const [configPath, setConfigPath] = useState<string>('');
   const [currentConfig, setCurrentConfig] = useState<Config | null>(null);
-  const [results, setResults] = useState<string[]>([]);
+  const [results, setResults] = useState<OrganizeResult[]>([]);
+  const [summary, setSummary] = useState<OrganizeSummary | null>(null);
+  const [estimatedSeconds, setEstimatedSeconds] = useState<number | null>(null);
+  const [ruleOrder, setRuleOrder] = useState<string[]>([]);
+  const [progress, setProgress] = useState<OrganizeProgress | null>(null);
+  const [fileProgress, setFileProgress] = useState<FileProgress | null>(null);
+  const [watching, setWatching] = useState<boolean>(false);
+  const [scheduled, setScheduled] = useState<boolean>(false);
+  const [appConfigDir, setAppConfigDir] = useState<string>('');
   const [status, setStatus] = useState<{message: string; type: StatusType}>({
     message: '',
     type: null
@@ -44,6 +229,77 @@ const App: React.FC = () => {
       }
     };
     loadSavedConfig();
+    invoke<string>('get_app_config_dir')
+      .then(setAppConfigDir)
+      .catch((error) => console.error('設定ディレクトリの取得に失敗しました:', error));
+  }, []);
+
+  // 整理処理の進捗イベントを購読する
+  useEffect(() => {
+    const unlisten = listen<OrganizeProgress>('organize-progress', (event) => {
+      setProgress(event.payload);
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  // 大きなファイルのコピーの進捗イベントを購読する (コピーが終わったら表示を消す)
+  useEffect(() => {
+    const unlisten = listen<FileProgress>('organize-file-progress', (event) => {
+      const { copied, total } = event.payload;
+      setFileProgress(copied < total ? event.payload : null);
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  // 移動件数が confirm_threshold を超えるルールの実行前に確認する
+  useEffect(() => {
+    const unlisten = listen<ConfirmRequest>('confirm-organize', async (event) => {
+      const { id, rule_name, count } = event.payload;
+      const approved = window.confirm(`ルール「${rule_name}」は ${count} 個のファイルを移動します。実行しますか？`);
+      try {
+        await invoke('confirm_organize', { id, approved });
+      } catch (error) {
+        console.error('確認結果の送信に失敗しました:', error);
+      }
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  // 監視モードで自動整理が行われたら通知する
+  useEffect(() => {
+    const unlisten = listen<OrganizeResult>('auto-organized', (event) => {
+      const result = event.payload;
+      setStatus({
+        message: `自動整理: ${result.rule_name} (${result.moved.length}個のファイルを移動しました)`,
+        type: result.errors.length > 0 ? 'error' : 'success'
+      });
+      setResults((previous) => [result, ...previous]);
+      setSummary(null);
+      setEstimatedSeconds(null);
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  // スケジュール実行が終わったら通知する
+  useEffect(() => {
+    const unlisten = listen<ScheduledRun>('scheduled-run', (event) => {
+      const run = event.payload;
+      const next = run.next_run ? ` / 次回: ${run.next_run}` : '';
+      setStatus(run.error
+        ? { message: `スケジュール実行 (${run.started_at}) に失敗しました: ${run.error}${next}`, type: 'error' }
+        : { message: `スケジュール実行 (${run.started_at}): ${run.moved}個のファイルを移動しました${next}`, type: 'success' });
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
   }, []);
 
   const loadConfigFromPath = async (path: string) => {
@@ -56,6 +312,8 @@ const App: React.FC = () => {
         type: 'success' 
       });
       setResults([]);
+      setSummary(null);
+      setEstimatedSeconds(null);
     } catch (error) {
       setStatus({ 
         message: `設定ファイルの読み込みに失敗しました: ${error}`, 
@@ -76,23 +334,46 @@ const App: React.FC = () => {
         type: 'success' 
       });
       setResults([]);
+      setSummary(null);
+      setEstimatedSeconds(null);
     } catch (error) {
       setStatus({ message: `ファイル選択または設定の読み込みに失敗しました: ${error}`, type: 'error' });
       setCurrentConfig(null);
     }
   };
 
-  const organizeFiles = async () => {
+  const organizeFiles = async (dryRun: boolean) => {
     if (!configPath) {
       setStatus({ message: '設定ファイルを選択してください', type: 'error' });
       return;
     }
 
     try {
-      setStatus({ message: 'ファイル整理を実行中...', type: 'loading' });
-      const results = await invoke<string[]>('organize_files', { configPath });
-      setResults(results);
-      setStatus({ message: 'ファイル整理が完了しました', type: 'success' });
+      setStatus({ 
+        message: dryRun ? 'プレビューを実行中...' : 'ファイル整理を実行中...', 
+        type: 'loading' 
+      });
+      setProgress(null);
+      setFileProgress(null);
+      const report = await invoke<OrganizeReport>('organize_files', { configPath, dryRun });
+      setResults(report.results);
+      setSummary(report.summary);
+      setEstimatedSeconds(report.estimated_seconds ?? null);
+      setRuleOrder(report.rule_order);
+      setProgress(null);
+      setFileProgress(null);
+      const postRun = report.post_run;
+      if (postRun && !postRun.success) {
+        setStatus({
+          message: `ファイル整理は完了しましたが、実行後コマンドが失敗しました: ${postRun.error ?? postRun.stderr}`,
+          type: 'error'
+        });
+        return;
+      }
+      setStatus({ 
+        message: dryRun ? 'プレビューが完了しました (ファイルは移動されていません)' : 'ファイル整理が完了しました', 
+        type: 'success' 
+      });
     } catch (error) {
       setStatus({ 
         message: `ファイル整理に失敗しました: ${error}`, 
@@ -101,12 +382,118 @@ const App: React.FC = () => {
     }
   };
 
+  // 1 つのルールだけを実行する (rule は設定ファイルでの番号)
+  const organizeSingleRule = async (rule: number, dryRun: boolean) => {
+    try {
+      setStatus({
+        message: dryRun ? 'プレビューを実行中...' : 'ファイル整理を実行中...',
+        type: 'loading'
+      });
+      setProgress(null);
+      setFileProgress(null);
+      const result = await invoke<OrganizeResult>('organize_single_rule', { configPath, rule, dryRun });
+      setResults([result]);
+      setSummary(null);
+      setEstimatedSeconds(null);
+      setRuleOrder([result.rule_name]);
+      setProgress(null);
+      setFileProgress(null);
+      setStatus({
+        message: `ルール「${result.rule_name}」の${dryRun ? 'プレビュー' : '実行'}が完了しました`,
+        type: result.errors.length > 0 ? 'error' : 'success'
+      });
+    } catch (error) {
+      setStatus({
+        message: `ファイル整理に失敗しました: ${error}`,
+        type: 'error'
+      });
+    }
+  };
+
+  const toggleWatching = async () => {
+    try {
+      if (watching) {
+        await invoke('stop_watching');
+        setWatching(false);
+        setStatus({ message: 'フォルダの監視を停止しました', type: 'success' });
+      } else {
+        if (!configPath) {
+          setStatus({ message: '設定ファイルを選択してください', type: 'error' });
+          return;
+        }
+        const folders = await invoke<string[]>('start_watching', { configPath });
+        setWatching(true);
+        setStatus({ message: `${folders.length}個のフォルダを監視しています`, type: 'success' });
+      }
+    } catch (error) {
+      setStatus({ message: `フォルダ監視の切り替えに失敗しました: ${error}`, type: 'error' });
+    }
+  };
+
+  const toggleSchedule = async () => {
+    try {
+      if (scheduled) {
+        await invoke('stop_schedule');
+        setScheduled(false);
+        setStatus({ message: 'スケジュール実行を停止しました', type: 'success' });
+      } else {
+        if (!configPath) {
+          setStatus({ message: '設定ファイルを選択してください', type: 'error' });
+          return;
+        }
+        const next = await invoke<string>('start_schedule', { configPath });
+        setScheduled(true);
+        setStatus({ message: `スケジュール実行を開始しました (次回: ${next})`, type: 'success' });
+      }
+    } catch (error) {
+      setStatus({ message: `スケジュール実行の切り替えに失敗しました: ${error}`, type: 'error' });
+    }
+  };
+
+  const forgetRecentConfigs = async () => {
+    try {
+      await invoke('clear_recent_configs');
+      setConfigPath('');
+      setStatus({ message: '最近使った設定ファイルの記録を消去しました', type: 'success' });
+    } catch (error) {
+      setStatus({ message: `記録の消去に失敗しました: ${error}`, type: 'error' });
+    }
+  };
+
+  const editConfig = async () => {
+    if (!configPath) {
+      setStatus({ message: '設定ファイルを選択してください', type: 'error' });
+      return;
+    }
+    try {
+      await invoke('edit_config', { configPath });
+    } catch (error) {
+      setStatus({ message: `${error}`, type: 'error' });
+    }
+  };
+
+  const revealAppConfigDir = async () => {
+    try {
+      await invoke('reveal_in_explorer', { path: appConfigDir });
+    } catch (error) {
+      setStatus({ message: `フォルダを開けませんでした: ${error}`, type: 'error' });
+    }
+  };
+
+  const cancelOrganize = async () => {
+    try {
+      await invoke('cancel_organize');
+    } catch (error) {
+      console.error('キャンセルに失敗しました:', error);
+    }
+  };
+
   return (
     <div className="container">
       <h1>🗂️ File Organizer</h1>
       
       <div className="form-group">
-        <label htmlFor="configPath">設定ファイル (YAML):</label>
+        <label htmlFor="configPath">設定ファイル (YAML / JSON / TOML):</label>
         <div className="button-group" style={{ display: 'flex', gap: '10px', alignItems: 'center' }}>
           <input
             type="text"
@@ -120,13 +507,28 @@ const App: React.FC = () => {
           <button type="button" className="btn-secondary" onClick={selectConfigFile} style={{ whiteSpace: 'nowrap' }}>
             📁 選択して読み込み
           </button>
+          <button type="button" className="btn-secondary" onClick={editConfig} style={{ whiteSpace: 'nowrap' }}>
+            ✏️ 編集
+          </button>
+          <button type="button" className="btn-secondary" onClick={forgetRecentConfigs} style={{ whiteSpace: 'nowrap' }}>
+            記録を消去
+          </button>
         </div>
       </div>
 
-      <div className="button-group" style={{ display: 'flex', justifyContent: 'center' }}>
-        <button type="button" className="btn-success" onClick={organizeFiles}>
+      <div className="button-group" style={{ display: 'flex', justifyContent: 'center', gap: '10px' }}>
+        <button type="button" className="btn-secondary" onClick={() => organizeFiles(true)}>
+          プレビュー (ドライラン)
+        </button>
+        <button type="button" className="btn-success" onClick={() => organizeFiles(false)}>
           ファイル整理実行
         </button>
+        <button type="button" className="btn-secondary" onClick={toggleWatching}>
+          {watching ? '監視を停止' : 'フォルダを監視'}
+        </button>
+        <button type="button" className="btn-secondary" onClick={toggleSchedule}>
+          {scheduled ? 'スケジュールを停止' : 'スケジュール実行'}
+        </button>
       </div>
 
       {status.type && (
@@ -135,14 +537,41 @@ const App: React.FC = () => {
         </div>
       )}
 
+      {status.type === 'loading' && progress && (
+        <div className="form-group">
+          <label>{progress.rule_name}: {progress.processed} / {progress.total}</label>
+          <progress value={progress.processed} max={Math.max(progress.total, 1)} style={{ width: '100%' }} />
+          <div style={{ fontSize: '0.85em', wordBreak: 'break-all' }}>{progress.current_file}</div>
+          {fileProgress && (
+            <>
+              <div style={{ fontSize: '0.85em', wordBreak: 'break-all' }}>
+                {fileProgress.file}: {Math.floor(fileProgress.copied / 1048576)} / {Math.floor(fileProgress.total / 1048576)} MB
+              </div>
+              <progress value={fileProgress.copied} max={Math.max(fileProgress.total, 1)} style={{ width: '100%' }} />
+            </>
+          )}
+          <button type="button" className="btn-secondary" onClick={cancelOrganize}>
+            キャンセル
+          </button>
+        </div>
+      )}
+
       {currentConfig && (
         <div className="results">
           <h3>📋 読み込まれた設定</h3>
           {currentConfig.rules.map((rule, index) => (
             <div key={index} className="result-item">
-              <strong>{rule.name}</strong><br />
-              📂 {rule.source_folder} → 📁 {rule.destination_folder}<br />
+              <strong>{rule.name}</strong>{rule.enabled === false && ' (無効)'}<br />
+              📂 {[rule.source_folder, ...(rule.source_folders ?? [])].filter(Boolean).join(', ')} → 📁 {rule.destination_folder}<br />
               🔍 パターン: <code>{rule.pattern}</code>
+              <div className="button-group" style={{ display: 'flex', gap: '10px', marginTop: '6px' }}>
+                <button type="button" className="btn-secondary" onClick={() => organizeSingleRule(index, true)}>
+                  このルールだけプレビュー
+                </button>
+                <button type="button" className="btn-secondary" onClick={() => organizeSingleRule(index, false)}>
+                  このルールだけ実行
+                </button>
+              </div>
             </div>
           ))}
         </div>
@@ -151,13 +580,55 @@ const App: React.FC = () => {
       {results.length > 0 && (
         <div className="results">
           <h3>📊 実行結果</h3>
+          {summary && (
+            <div className="result-item">
+              合計: {summary.total_moved}個のファイル ({formatBytes(summary.total_bytes)}) / 所要時間: {(summary.elapsed_ms / 1000).toFixed(1)}秒
+              {estimatedSeconds !== null && <><br />実行した場合の所要時間 (目安): 約{Math.ceil(estimatedSeconds)}秒</>}
+              {ruleOrder.length > 1 && <><br />実行順: {ruleOrder.join(' → ')}</>}
+            </div>
+          )}
           {results.map((result, index) => (
             <div key={index} className="result-item">
-              {result}
+              <strong>{result.rule_name}</strong>: {result.moved.length}個のファイルを{result.dry_run ? '移動予定' : '移動しました'}
+              {result.moved.map((file, i) => (
+                <div key={`moved-${i}`}>
+                  {result.dry_run ? '移動予定' : file.strategy === 'copy' ? 'コピー' : file.strategy.startsWith('gzip') || file.strategy === 'zip' ? '圧縮' : file.strategy === 'symlink' || file.strategy === 'hardlink' ? 'リンク' : `移動 (${file.strategy})`}: {file.source} → {file.destination}
+                </div>
+              ))}
+              {result.archives.map((archive, i) => (
+                <div key={`archive-${i}`}>
+                  圧縮: {archive.path} ({archive.files}個, {formatBytes(archive.original_size)} → {formatBytes(archive.compressed_size)}, {(archive.ratio * 100).toFixed(1)}%)
+                </div>
+              ))}
+              {result.skipped && result.skipped.length > 0 && (
+                <details>
+                  <summary>移動しなかったファイル ({result.skipped.length}個)</summary>
+                  {result.skipped.map((skipped, i) => (
+                    <div key={`skipped-${i}`} style={{ fontSize: '0.85em', wordBreak: 'break-all' }}>
+                      {skipped.path}: {SKIP_REASON_LABELS[skipped.reason]}{skipped.message && ` (${skipped.message})`}
+                    </div>
+                  ))}
+                </details>
+              )}
+              {result.warnings.map((warning, i) => (
+                <div key={`warning-${i}`} style={{ color: '#b7791f' }}>⚠️ {warning}</div>
+              ))}
+              {result.errors.map((error, i) => (
+                <div key={`error-${i}`} style={{ color: '#c53030' }}>❌ {error}</div>
+              ))}
             </div>
           ))}
         </div>
       )}
+
+      {appConfigDir && (
+        <div className="form-group" style={{ display: 'flex', gap: '10px', alignItems: 'center', fontSize: '0.9em' }}>
+          <span style={{ flex: 1, minWidth: 0, overflowWrap: 'anywhere' }}>⚙️ アプリの設定の保存先: {appConfigDir}</span>
+          <button type="button" className="btn-secondary" onClick={revealAppConfigDir} style={{ whiteSpace: 'nowrap' }}>
+            フォルダを開く
+          </button>
+        </div>
+      )}
     </div>
   );
 };